@@ -0,0 +1,326 @@
+use anyhow::{anyhow, bail, Context, Result};
+use async_lock::RwLock;
+use celestia_rpc::blob::BlobsAtHeight;
+use celestia_rpc::{BlobClient, Client, HeaderClient};
+use celestia_types::nmt::Namespace;
+use celestia_types::Blob;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_stream::{Stream, StreamExt};
+
+/// How long a health check waits for a Celestia node to respond.
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long connecting to a Celestia node may take.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A Celestia node the rollup node talks to.
+struct Endpoint {
+    url: String,
+    /// Not set while the node can not be connected to
+    client: RwLock<Option<Arc<Client>>>,
+    healthy: AtomicBool,
+}
+
+/// Connections to one or more Celestia nodes.
+///
+/// Requests go to the endpoint that answered last and fail over to the other
+/// endpoints in order, healthy ones first. Endpoints that fail a request are
+/// marked unhealthy until the next successful [`DaClient::check_health`],
+/// which also reconnects to endpoints that went down.
+pub struct DaClient {
+    endpoints: Vec<Endpoint>,
+    auth_token: Option<String>,
+    /// Compare the blobs of each height with a second endpoint
+    cross_check: bool,
+    /// Index of the endpoint that answered last
+    preferred: AtomicUsize,
+}
+
+impl DaClient {
+    /// Connects to the given Celestia nodes. Fails only if none of them can
+    /// be reached.
+    pub async fn connect(
+        urls: &[String],
+        auth_token: Option<String>,
+        cross_check: bool,
+    ) -> Result<Self> {
+        if urls.is_empty() {
+            bail!("at least one Celestia node URL is required");
+        }
+
+        let mut endpoints = Vec::with_capacity(urls.len());
+        for url in urls {
+            let client = match connect_client(url, auth_token.as_deref()).await {
+                Ok(client) => Some(client),
+                Err(e) => {
+                    warn!("{:#}", e);
+                    None
+                }
+            };
+            endpoints.push(Endpoint {
+                url: url.clone(),
+                healthy: AtomicBool::new(client.is_some()),
+                client: RwLock::new(client),
+            });
+        }
+
+        let Some(preferred) = endpoints
+            .iter()
+            .position(|e| e.healthy.load(Ordering::Relaxed))
+        else {
+            bail!("Couldn't start RPC connection to any celestia-node instance");
+        };
+        if cross_check && endpoints.len() < 2 {
+            warn!("cross-checking reads needs a second Celestia node, reads are not checked");
+        }
+
+        Ok(DaClient {
+            endpoints,
+            auth_token,
+            cross_check,
+            preferred: AtomicUsize::new(preferred),
+        })
+    }
+
+    /// Sends a request, failing over to the other endpoints if it fails.
+    ///
+    /// Any error leads to a failover, so a submission that timed out may be
+    /// included twice. Enforce nonces in [`crate::state::State`] to reject
+    /// the second copy of each transaction.
+    pub async fn request<T, E, F, Fut>(&self, f: F) -> Result<T>
+    where
+        F: Fn(Arc<Client>) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        E: Into<anyhow::Error>,
+    {
+        self.request_from(None, f).await.map(|(res, _)| res)
+    }
+
+    /// Like [`DaClient::request`], but skips the endpoint at `exclude` and
+    /// returns the index of the endpoint that answered.
+    async fn request_from<T, E, F, Fut>(&self, exclude: Option<usize>, f: F) -> Result<(T, usize)>
+    where
+        F: Fn(Arc<Client>) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        E: Into<anyhow::Error>,
+    {
+        let mut last_error = anyhow!("no Celestia node available");
+
+        for index in self.failover_order(exclude) {
+            let endpoint = &self.endpoints[index];
+            let client = match self.client(index).await {
+                Ok(client) => client,
+                Err(e) => {
+                    last_error = e;
+                    continue;
+                }
+            };
+
+            match f(client).await {
+                Ok(res) => {
+                    endpoint.healthy.store(true, Ordering::Relaxed);
+                    if exclude.is_none() && self.preferred.swap(index, Ordering::Relaxed) != index {
+                        info!("using Celestia node {}", endpoint.url);
+                    }
+                    return Ok((res, index));
+                }
+                Err(e) => {
+                    let e = e.into();
+                    warn!("request to Celestia node {} failed: {}", endpoint.url, e);
+                    endpoint.healthy.store(false, Ordering::Relaxed);
+                    last_error = e;
+                }
+            }
+        }
+
+        Err(last_error.context("all Celestia nodes failed"))
+    }
+
+    /// Returns the endpoints to try, starting with the preferred one, healthy
+    /// endpoints before unhealthy ones.
+    fn failover_order(&self, exclude: Option<usize>) -> Vec<usize> {
+        let start = self.preferred.load(Ordering::Relaxed);
+        let mut order: Vec<usize> = (0..self.endpoints.len())
+            .map(|offset| (start + offset) % self.endpoints.len())
+            .filter(|index| Some(*index) != exclude)
+            .collect();
+        // stable, so the rotation is kept within both groups
+        order.sort_by_key(|index| !self.endpoints[*index].healthy.load(Ordering::Relaxed));
+        order
+    }
+
+    /// Returns the client of an endpoint, connecting to it if needed.
+    async fn client(&self, index: usize) -> Result<Arc<Client>> {
+        let endpoint = &self.endpoints[index];
+        if let Some(client) = endpoint.client.read().await.as_ref() {
+            return Ok(client.clone());
+        }
+
+        // connect without holding the lock, so a node that does not respond
+        // does not block requests that only read the client
+        let connected = connect_client(&endpoint.url, self.auth_token.as_deref()).await?;
+        let mut client = endpoint.client.write().await;
+        if let Some(client) = client.as_ref() {
+            // another request connected in the meantime
+            return Ok(client.clone());
+        }
+        info!("connected to Celestia node {}", endpoint.url);
+        *client = Some(connected.clone());
+        Ok(connected)
+    }
+
+    /// Returns all blobs in `namespace` at `height`. If cross-checking is
+    /// enabled, the blobs are compared with those of a second endpoint, and a
+    /// mismatch is an error.
+    pub async fn get_blobs(&self, height: u64, namespace: Namespace) -> Result<Vec<Blob>> {
+        let get_all = move |client: Arc<Client>| async move {
+            BlobClient::blob_get_all(&*client, height, &[namespace]).await
+        };
+        let (blobs, index) = self.request_from(None, get_all).await?;
+        let blobs = blobs.unwrap_or_default();
+        if !self.cross_check || self.endpoints.len() < 2 {
+            return Ok(blobs);
+        }
+
+        match self.request_from(Some(index), get_all).await {
+            Ok((other, other_index)) => {
+                if !same_blobs(&blobs, &other.unwrap_or_default()) {
+                    bail!(
+                        "Celestia nodes {} and {} returned different blobs at height {}",
+                        self.endpoints[index].url,
+                        self.endpoints[other_index].url,
+                        height
+                    );
+                }
+            }
+            Err(e) => warn!("cross-checking blobs at height {}: {}", height, e),
+        }
+        Ok(blobs)
+    }
+
+    /// Subscribes to the blobs in `namespace` at the preferred endpoint, or
+    /// the next one that accepts the subscription.
+    pub async fn subscribe(
+        &self,
+        namespace: Namespace,
+    ) -> Result<impl Stream<Item = Result<BlobsAtHeight>> + Unpin> {
+        let subscription = self
+            .request(|client| async move { BlobClient::blob_subscribe(&*client, namespace).await })
+            .await?;
+        Ok(subscription.map(|res| res.map_err(anyhow::Error::from)))
+    }
+
+    /// Probes every endpoint and reconnects to those that do not respond.
+    /// Returns the number of healthy endpoints.
+    pub async fn check_health(&self) -> usize {
+        let mut healthy_count = 0;
+        for (index, endpoint) in self.endpoints.iter().enumerate() {
+            let healthy = match self.client(index).await {
+                Ok(client) => {
+                    let request = HeaderClient::header_network_head(&*client);
+                    matches!(
+                        tokio::time::timeout(HEALTH_CHECK_TIMEOUT, request).await,
+                        Ok(Ok(_))
+                    )
+                }
+                Err(_) => false,
+            };
+
+            if !healthy {
+                // a dropped websocket does not recover, so connect again on
+                // the next use
+                *endpoint.client.write().await = None;
+            }
+            if healthy != endpoint.healthy.swap(healthy, Ordering::Relaxed) {
+                if healthy {
+                    info!("Celestia node {} is healthy again", endpoint.url);
+                } else {
+                    warn!("Celestia node {} is unhealthy", endpoint.url);
+                }
+            }
+            healthy_count += healthy as usize;
+        }
+        healthy_count
+    }
+
+    /// Returns true if at least one endpoint is healthy.
+    pub fn is_healthy(&self) -> bool {
+        self.endpoints
+            .iter()
+            .any(|endpoint| endpoint.healthy.load(Ordering::Relaxed))
+    }
+}
+
+/// Connects to a Celestia node, giving up after [`CONNECT_TIMEOUT`].
+async fn connect_client(url: &str, auth_token: Option<&str>) -> Result<Arc<Client>> {
+    let client = tokio::time::timeout(CONNECT_TIMEOUT, Client::new(url, auth_token))
+        .await
+        .map_err(|_| anyhow!("timed out after {:?}", CONNECT_TIMEOUT))
+        .and_then(|res| res.map_err(anyhow::Error::from))
+        .with_context(|| format!("Failed to connect to Celestia node {}", url))?;
+    Ok(Arc::new(client))
+}
+
+/// Returns true if both lists hold the same blobs. All fields the rollup
+/// reads are compared, as the commitment of a blob returned by a node is
+/// not checked against its data, share version or signer.
+pub(crate) fn same_blobs(blobs: &[Blob], other: &[Blob]) -> bool {
+    blobs.len() == other.len()
+        && blobs.iter().zip(other).all(|(blob, other)| {
+            blob.namespace == other.namespace
+                && blob.commitment == other.commitment
+                && blob.data == other.data
+                && blob.share_version == other.share_version
+                && blob.signer == other.signer
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::APP_VERSION;
+    use celestia_types::state::AccAddress;
+
+    fn namespace() -> Namespace {
+        Namespace::new_v0(&[1, 2, 3]).unwrap()
+    }
+
+    fn signer(address: &str) -> AccAddress {
+        address.parse().unwrap()
+    }
+
+    #[test]
+    fn same_blobs_compares_data() {
+        let blob = Blob::new(namespace(), vec![1, 2, 3], APP_VERSION).unwrap();
+        assert!(same_blobs(&[blob.clone()], &[blob.clone()]));
+        assert!(!same_blobs(&[blob.clone()], &[]));
+
+        let mut other = blob.clone();
+        other.data = vec![4, 5, 6];
+        assert!(!same_blobs(&[blob], &[other]));
+    }
+
+    #[test]
+    fn same_blobs_compares_signers() {
+        let blob = Blob::new_with_signer(
+            namespace(),
+            vec![1, 2, 3],
+            signer("celestia1qyqszqgpqyqszqgpqyqszqgpqyqszqgpreswh3"),
+            APP_VERSION,
+        )
+        .unwrap();
+
+        // a node can attach another signer without changing the commitment
+        let mut other = blob.clone();
+        other.signer = Some(signer("celestia1qgpqyqszqgpqyqszqgpqyqszqgpqyqszjaktu8"));
+        assert!(!same_blobs(&[blob.clone()], &[other]));
+
+        let mut unsigned = blob.clone();
+        unsigned.signer = None;
+        unsigned.share_version = 0;
+        assert!(!same_blobs(&[blob], &[unsigned]));
+    }
+}
@@ -0,0 +1,218 @@
+use anyhow::{Context, Result};
+use async_lock::Mutex;
+use celestia_types::{blob::Commitment, Blob};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Number of dead letters kept in memory. Older ones are only kept in the
+/// dead-letter file, if configured.
+const DEAD_LETTER_CAPACITY: usize = 10_000;
+
+/// Where a blob was included on Celestia.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BlobRef {
+    pub da_height: u64,
+    pub blob_index: usize,
+    pub commitment: Commitment,
+}
+
+impl BlobRef {
+    pub fn new(da_height: u64, blob_index: usize, blob: &Blob) -> Self {
+        BlobRef {
+            da_height,
+            blob_index,
+            commitment: blob.commitment.clone(),
+        }
+    }
+}
+
+/// A blob or transaction from the rollup namespace that was rejected during
+/// sync.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DeadLetter {
+    #[serde(flatten)]
+    pub blob: BlobRef,
+    /// Hash of the rejected transaction, or unset if the whole blob was
+    /// rejected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<String>,
+    pub reason: String,
+}
+
+impl DeadLetter {
+    pub fn blob(blob: BlobRef, reason: impl ToString) -> Self {
+        DeadLetter {
+            blob,
+            tx_hash: None,
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn transaction(blob: BlobRef, tx_hash: String, reason: impl ToString) -> Self {
+        DeadLetter {
+            blob,
+            tx_hash: Some(tx_hash),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+/// Keeps the most recent dead letters in memory, and appends them to a JSON
+/// lines file if one is configured.
+pub struct DeadLetterStore {
+    entries: Mutex<VecDeque<DeadLetter>>,
+    file: Option<PathBuf>,
+}
+
+impl DeadLetterStore {
+    /// Creates the store, loading the most recent entries of `file` if it
+    /// exists.
+    pub fn open(file: Option<PathBuf>) -> Result<Self> {
+        let mut entries = VecDeque::new();
+        if let Some(path) = file.as_ref().filter(|path| path.exists()) {
+            let content = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read dead letters from {}", path.display()))?;
+            for line in content.lines().filter(|line| !line.is_empty()) {
+                entries.push_back(serde_json::from_str(line).with_context(|| {
+                    format!("Failed to decode dead letter in {}", path.display())
+                })?);
+                if entries.len() > DEAD_LETTER_CAPACITY {
+                    entries.pop_front();
+                }
+            }
+        }
+
+        Ok(DeadLetterStore {
+            entries: Mutex::new(entries),
+            file,
+        })
+    }
+
+    /// Records a dead letter. Returns false if it was recorded before, e.g.
+    /// when the node syncs the same heights again.
+    pub async fn record(&self, letter: DeadLetter) -> bool {
+        let mut entries = self.entries.lock().await;
+        if entries.contains(&letter) {
+            return false;
+        }
+
+        warn!(
+            da_height = letter.blob.da_height,
+            blob_index = letter.blob.blob_index,
+            tx_hash = ?letter.tx_hash,
+            "dead letter: {}",
+            letter.reason
+        );
+        if let Some(path) = &self.file {
+            if let Err(e) = append(path, &letter) {
+                error!("writing dead letter to {}: {}", path.display(), e);
+            }
+        }
+
+        entries.push_back(letter);
+        if entries.len() > DEAD_LETTER_CAPACITY {
+            entries.pop_front();
+        }
+        true
+    }
+
+    /// Returns up to `limit` dead letters from `from_height` on, oldest first.
+    pub async fn list(&self, from_height: u64, limit: usize) -> Vec<DeadLetter> {
+        self.entries
+            .lock()
+            .await
+            .iter()
+            .filter(|letter| letter.blob.da_height >= from_height)
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+fn append(path: &Path, letter: &DeadLetter) -> Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(letter)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::APP_VERSION;
+    use celestia_types::nmt::Namespace;
+
+    fn letter(da_height: u64, reason: &str) -> DeadLetter {
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let blob = Blob::new(namespace, da_height.to_be_bytes().to_vec(), APP_VERSION).unwrap();
+        DeadLetter::blob(BlobRef::new(da_height, 0, &blob), reason)
+    }
+
+    /// Returns a path in the temporary directory of this test run, with no
+    /// file at it yet.
+    fn temp_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("shard-dead-letters-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[tokio::test]
+    async fn records_each_dead_letter_once() {
+        let store = DeadLetterStore::open(None).unwrap();
+        assert!(store.record(letter(1, "undecodable blob")).await);
+        assert!(!store.record(letter(1, "undecodable blob")).await);
+        assert!(store.record(letter(1, "another reason")).await);
+        assert_eq!(store.list(0, usize::MAX).await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn lists_from_a_height_up_to_a_limit() {
+        let store = DeadLetterStore::open(None).unwrap();
+        for da_height in 1..=5 {
+            store.record(letter(da_height, "rejected")).await;
+        }
+
+        let heights: Vec<u64> = store
+            .list(2, 3)
+            .await
+            .iter()
+            .map(|letter| letter.blob.da_height)
+            .collect();
+        assert_eq!(heights, [2, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn evicts_the_oldest_dead_letters_beyond_capacity() {
+        let store = DeadLetterStore::open(None).unwrap();
+        for da_height in 0..=DEAD_LETTER_CAPACITY as u64 {
+            store.record(letter(da_height, "rejected")).await;
+        }
+
+        let letters = store.list(0, usize::MAX).await;
+        assert_eq!(letters.len(), DEAD_LETTER_CAPACITY);
+        assert_eq!(letters[0].blob.da_height, 1);
+    }
+
+    #[tokio::test]
+    async fn reloads_dead_letters_from_the_file() {
+        let path = temp_file("reload.jsonl");
+        let store = DeadLetterStore::open(Some(path.clone())).unwrap();
+        store.record(letter(1, "undecodable blob")).await;
+        store.record(letter(2, "invalid nonce")).await;
+        drop(store);
+
+        let store = DeadLetterStore::open(Some(path.clone())).unwrap();
+        assert_eq!(
+            store.list(0, usize::MAX).await,
+            [letter(1, "undecodable blob"), letter(2, "invalid nonce")]
+        );
+        // syncing the same heights again after a restart records nothing
+        assert!(!store.record(letter(2, "invalid nonce")).await);
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,247 @@
+use anyhow::{anyhow, bail, Context, Result};
+use celestia_types::Blob;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+
+use crate::sequencer::SignedBatch;
+use crate::tx::{Batch, Transaction};
+
+/// Marks blobs that start with an envelope header. Headerless blobs from
+/// before the envelope start with a bincode length prefix or enum tag, which
+/// never equals these bytes.
+pub const MAGIC: [u8; 4] = *b"SHRD";
+
+/// The only envelope format version understood by this node.
+pub const FORMAT_VERSION: u8 = 1;
+
+/// Size of the envelope header: magic bytes, version, compression and payload
+/// kind.
+pub const HEADER_LEN: usize = MAGIC.len() + 3;
+
+/// Upper bound for decompressed payloads, so a small blob can not make the
+/// node allocate unbounded memory.
+const MAX_DECOMPRESSED_SIZE: usize = 64 * 1024 * 1024;
+
+const ZSTD_LEVEL: i32 = 3;
+
+/// How the payload of an envelope is compressed.
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    None,
+    #[default]
+    Zstd,
+}
+
+impl TryFrom<u8> for Compression {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(Compression::None),
+            1 => Ok(Compression::Zstd),
+            _ => Err(anyhow!("unknown compression {}", value)),
+        }
+    }
+}
+
+/// What the payload of an envelope decodes to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PayloadKind {
+    Batch = 0,
+    Transaction = 1,
+    SignedBatch = 2,
+}
+
+impl TryFrom<u8> for PayloadKind {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(PayloadKind::Batch),
+            1 => Ok(PayloadKind::Transaction),
+            2 => Ok(PayloadKind::SignedBatch),
+            _ => Err(anyhow!("unknown payload kind {}", value)),
+        }
+    }
+}
+
+/// Wraps a bincode encoded payload into an envelope. The payload is stored
+/// uncompressed if compression does not make it smaller.
+pub fn seal(kind: PayloadKind, payload: &[u8], compression: Compression) -> Result<Vec<u8>> {
+    let (compression, payload) = match compression {
+        Compression::Zstd => {
+            let compressed = zstd::bulk::compress(payload, ZSTD_LEVEL)?;
+            if compressed.len() < payload.len() {
+                (Compression::Zstd, compressed)
+            } else {
+                (Compression::None, payload.to_vec())
+            }
+        }
+        Compression::None => (Compression::None, payload.to_vec()),
+    };
+
+    let mut data = Vec::with_capacity(HEADER_LEN + payload.len());
+    data.extend_from_slice(&MAGIC);
+    data.push(FORMAT_VERSION);
+    data.push(compression as u8);
+    data.push(kind as u8);
+    data.extend_from_slice(&payload);
+    Ok(data)
+}
+
+/// Unwraps an envelope and returns the kind and decompressed payload, or
+/// `None` for legacy headerless blobs.
+pub fn open(data: &[u8]) -> Result<Option<(PayloadKind, Vec<u8>)>> {
+    let Some(header) = data.strip_prefix(&MAGIC) else {
+        return Ok(None);
+    };
+    let [version, compression, kind, ..] = *header else {
+        bail!("truncated envelope header");
+    };
+    if version != FORMAT_VERSION {
+        bail!("unsupported blob format version {}", version);
+    }
+
+    let kind = PayloadKind::try_from(kind)?;
+    let payload = &header[3..];
+    let payload = match Compression::try_from(compression)? {
+        Compression::None => payload.to_vec(),
+        Compression::Zstd => decompress(payload)?,
+    };
+    Ok(Some((kind, payload)))
+}
+
+/// Decompresses a zstd payload, failing once it exceeds
+/// [`MAX_DECOMPRESSED_SIZE`]. Memory grows with the actual output instead of
+/// being reserved for the limit up front.
+fn decompress(payload: &[u8]) -> Result<Vec<u8>> {
+    let decoder = zstd::stream::read::Decoder::new(payload).context("Failed to decompress blob")?;
+    let mut decompressed = Vec::new();
+    decoder
+        .take(MAX_DECOMPRESSED_SIZE as u64 + 1)
+        .read_to_end(&mut decompressed)
+        .context("Failed to decompress blob")?;
+    if decompressed.len() > MAX_DECOMPRESSED_SIZE {
+        bail!("decompressed blob exceeds {} bytes", MAX_DECOMPRESSED_SIZE);
+    }
+    Ok(decompressed)
+}
+
+/// Contents of a rollup blob.
+pub enum BlobPayload {
+    /// Unsigned transactions, from a batch or a single transaction
+    Transactions(Vec<Transaction>),
+    Signed(SignedBatch),
+}
+
+impl BlobPayload {
+    pub fn into_transactions(self) -> Vec<Transaction> {
+        match self {
+            BlobPayload::Transactions(txs) => txs,
+            BlobPayload::Signed(batch) => batch.transactions,
+        }
+    }
+}
+
+impl TryFrom<&Blob> for BlobPayload {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &Blob) -> Result<Self, Self::Error> {
+        let Some((kind, payload)) = open(&value.data)? else {
+            let batch = Batch::try_from(value)?;
+            return Ok(BlobPayload::Transactions(batch.get_transactions()));
+        };
+
+        Ok(match kind {
+            PayloadKind::Batch => {
+                let batch: Batch = bincode::deserialize(&payload)?;
+                BlobPayload::Transactions(batch.get_transactions())
+            }
+            PayloadKind::Transaction => {
+                // a blob with a single transaction was posted by its sender,
+                // so the blob signer vouches for it
+                let mut tx: Transaction = bincode::deserialize(&payload)?;
+                tx.blob_signer = value.signer.clone();
+                BlobPayload::Transactions(vec![tx])
+            }
+            PayloadKind::SignedBatch => BlobPayload::Signed(bincode::deserialize(&payload)?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let payload = vec![7u8; 1000];
+        for compression in [Compression::None, Compression::Zstd] {
+            let data = seal(PayloadKind::Batch, &payload, compression).unwrap();
+            assert_eq!(&data[..MAGIC.len()], MAGIC);
+            assert_eq!(data[MAGIC.len() + 1], compression as u8);
+            assert_eq!(
+                open(&data).unwrap(),
+                Some((PayloadKind::Batch, payload.clone()))
+            );
+        }
+    }
+
+    #[test]
+    fn falls_back_to_uncompressed_payloads() {
+        // too short for zstd to save anything
+        let payload = [1, 2, 3];
+        let data = seal(PayloadKind::Transaction, &payload, Compression::Zstd).unwrap();
+        assert_eq!(data[MAGIC.len() + 1], Compression::None as u8);
+        assert_eq!(&data[HEADER_LEN..], payload);
+        assert_eq!(
+            open(&data).unwrap(),
+            Some((PayloadKind::Transaction, payload.to_vec()))
+        );
+    }
+
+    #[test]
+    fn legacy_blobs_have_no_envelope() {
+        let legacy = bincode::serialize(&vec![1u64, 2, 3]).unwrap();
+        assert_eq!(open(&legacy).unwrap(), None);
+        assert_eq!(open(&[]).unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_unknown_versions_and_kinds() {
+        let mut data = seal(PayloadKind::Batch, &[1, 2, 3], Compression::None).unwrap();
+        data[MAGIC.len()] = FORMAT_VERSION + 1;
+        assert!(open(&data).is_err());
+
+        let mut data = seal(PayloadKind::Batch, &[1, 2, 3], Compression::None).unwrap();
+        data[MAGIC.len() + 1] = 9;
+        assert!(open(&data).is_err());
+
+        let mut data = seal(PayloadKind::Batch, &[1, 2, 3], Compression::None).unwrap();
+        data[MAGIC.len() + 2] = 9;
+        assert!(open(&data).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_headers() {
+        let data = seal(PayloadKind::Batch, &[], Compression::None).unwrap();
+        for len in MAGIC.len() + 1..HEADER_LEN {
+            assert!(open(&data[..len]).is_err());
+        }
+        assert_eq!(open(&data).unwrap(), Some((PayloadKind::Batch, Vec::new())));
+    }
+
+    #[test]
+    fn rejects_corrupt_and_oversized_payloads() {
+        let mut data = seal(PayloadKind::Batch, &[0; 1000], Compression::Zstd).unwrap();
+        data.truncate(data.len() - 4);
+        assert!(open(&data).is_err());
+
+        let payload = vec![0; MAX_DECOMPRESSED_SIZE + 1];
+        let data = seal(PayloadKind::Batch, &payload, Compression::Zstd).unwrap();
+        assert!(data.len() < 64 * 1024);
+        assert!(open(&data).is_err());
+    }
+}
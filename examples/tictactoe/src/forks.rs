@@ -0,0 +1,86 @@
+use anyhow::{bail, Result};
+use celestia_types::Blob;
+
+use crate::envelope::BlobPayload;
+
+/// Version of the transaction encoding and state transition rules.
+pub type ProtocolVersion = u32;
+
+/// A protocol upgrade, activated at a DA height.
+pub struct Fork {
+    pub name: &'static str,
+    /// First DA height processed under this fork
+    pub activation_height: u64,
+    pub version: ProtocolVersion,
+}
+
+/// The upgrade schedule of the rollup, ordered by activation height. All nodes
+/// must use the same schedule, or they will compute different states.
+///
+/// To change the wire format of [`crate::tx::TransactionType`] or the state
+/// rules without breaking nodes that sync from the start, keep the old types
+/// and rules around, append a fork with a new version here, and handle older
+/// versions in [`decode_blob`] and [`crate::state::State::process_tx`].
+pub const FORKS: &[Fork] = &[Fork {
+    name: "genesis",
+    activation_height: 0,
+    version: 0,
+}];
+
+/// Returns the fork active at `height`.
+pub fn fork_at(height: u64) -> &'static Fork {
+    FORKS
+        .iter()
+        .rev()
+        .find(|fork| fork.activation_height <= height)
+        .unwrap_or(&FORKS[0])
+}
+
+/// Decodes a blob that was included under `version`. Every version in
+/// [`FORKS`] needs an arm here. After a fork that changes the wire format,
+/// decode blobs of older versions with the old transaction types and convert
+/// them to the current ones, e.g. `0 => decode_v0(blob)`.
+pub fn decode_blob(version: ProtocolVersion, blob: &Blob) -> Result<BlobPayload> {
+    match version {
+        0 => BlobPayload::try_from(blob),
+        _ => bail!("no blob decoder for protocol version {}", version),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::envelope::{self, Compression, PayloadKind};
+    use crate::node::APP_VERSION;
+    use crate::tx::Batch;
+    use celestia_types::nmt::Namespace;
+
+    #[test]
+    fn forks_are_ordered_by_activation_height() {
+        assert_eq!(FORKS[0].activation_height, 0);
+        for forks in FORKS.windows(2) {
+            assert!(forks[0].activation_height < forks[1].activation_height);
+            assert!(forks[0].version < forks[1].version);
+        }
+        for fork in FORKS {
+            assert_eq!(fork_at(fork.activation_height).version, fork.version);
+        }
+    }
+
+    #[test]
+    fn every_fork_has_a_blob_decoder() {
+        let data = envelope::seal(
+            PayloadKind::Batch,
+            &bincode::serialize(&Batch::new(Vec::new())).unwrap(),
+            Compression::None,
+        )
+        .unwrap();
+        let namespace = Namespace::new_v0(&[1, 2, 3]).unwrap();
+        let blob = Blob::new(namespace, data, APP_VERSION).unwrap();
+
+        for fork in FORKS {
+            assert!(decode_blob(fork.version, &blob).is_ok(), "{}", fork.name);
+        }
+        assert!(decode_blob(ProtocolVersion::MAX, &blob).is_err());
+    }
+}
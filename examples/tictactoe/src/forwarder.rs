@@ -0,0 +1,137 @@
+use anyhow::{anyhow, Result};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crate::tx::Transaction;
+
+/// Delay before the first retry round, doubled for every following round.
+const RETRY_BACKOFF: Duration = Duration::from_millis(200);
+
+/// Upper bound for the delay between retry rounds.
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Timeout for a single request to an upstream sequencer.
+const UPSTREAM_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Response of an upstream sequencer, passed back to the client unchanged.
+pub struct UpstreamResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+/// Forwards transactions submitted to a follower node to the upstream
+/// sequencers.
+///
+/// Upstreams are tried in order, starting with the last one that answered.
+/// An upstream is skipped if it is unreachable or responds with 502, 503 or
+/// 504; any other response is returned as is. If no upstream answers, the
+/// whole list is retried with exponential backoff, up to
+/// [`MAX_RETRY_BACKOFF`] between rounds.
+pub struct Forwarder {
+    client: reqwest::Client,
+    upstreams: Vec<String>,
+    retries: u32,
+    /// Index of the upstream that answered last
+    preferred: AtomicUsize,
+}
+
+impl Forwarder {
+    pub fn new(upstreams: Vec<String>, retries: u32) -> Result<Self> {
+        if upstreams.is_empty() {
+            return Err(anyhow!("at least one upstream sequencer is required"));
+        }
+
+        let client = reqwest::Client::builder()
+            .timeout(UPSTREAM_TIMEOUT)
+            .build()?;
+
+        Ok(Forwarder {
+            client,
+            upstreams: upstreams
+                .into_iter()
+                .map(|url| url.trim_end_matches('/').to_string())
+                .collect(),
+            retries,
+            preferred: AtomicUsize::new(0),
+        })
+    }
+
+    pub async fn forward(&self, tx: &Transaction) -> Result<UpstreamResponse> {
+        let mut last_error = anyhow!("no upstream sequencer configured");
+
+        for round in 0..=self.retries {
+            if round > 0 {
+                tokio::time::sleep(retry_backoff(round)).await;
+            }
+
+            let start = self.preferred.load(Ordering::Relaxed);
+            for offset in 0..self.upstreams.len() {
+                let index = (start + offset) % self.upstreams.len();
+                let upstream = &self.upstreams[index];
+
+                match self.try_forward(upstream, tx).await {
+                    Ok(response) => {
+                        self.preferred.store(index, Ordering::Relaxed);
+                        return Ok(response);
+                    }
+                    Err(e) => {
+                        warn!("forwarding tx to {}: {}", upstream, e);
+                        last_error = e;
+                    }
+                }
+            }
+        }
+
+        Err(last_error.context("all upstream sequencers failed"))
+    }
+
+    async fn try_forward(&self, upstream: &str, tx: &Transaction) -> Result<UpstreamResponse> {
+        let response = self
+            .client
+            .post(format!("{}/submit_tx", upstream))
+            .json(tx)
+            .send()
+            .await?;
+
+        let status = response.status().as_u16();
+        if matches!(status, 502..=504) {
+            return Err(anyhow!("upstream unavailable ({})", status));
+        }
+
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let body = response.bytes().await?.to_vec();
+
+        debug!("forwarded tx to {} ({})", upstream, status);
+        Ok(UpstreamResponse {
+            status,
+            content_type,
+            body,
+        })
+    }
+}
+
+/// Returns the delay before retry round `round`, counted from 1.
+fn retry_backoff(round: u32) -> Duration {
+    RETRY_BACKOFF
+        .saturating_mul(2u32.saturating_pow(round.saturating_sub(1)))
+        .min(MAX_RETRY_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_maximum() {
+        assert_eq!(retry_backoff(1), RETRY_BACKOFF);
+        assert_eq!(retry_backoff(2), RETRY_BACKOFF * 2);
+        assert_eq!(retry_backoff(3), RETRY_BACKOFF * 4);
+        assert_eq!(retry_backoff(40), MAX_RETRY_BACKOFF);
+        assert_eq!(retry_backoff(u32::MAX), MAX_RETRY_BACKOFF);
+    }
+}
@@ -0,0 +1,119 @@
+use anyhow::Result;
+use prometheus::{
+    Counter, Encoder, Histogram, HistogramOpts, IntCounter, IntGauge, Registry, TextEncoder,
+};
+
+/// Prometheus metrics exported by the node on `/metrics`.
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+
+    /// The last Celestia height processed by the node.
+    pub da_height: IntGauge,
+    /// The latest Celestia height known to the node.
+    pub network_head: IntGauge,
+    /// Number of Celestia nodes that passed the last health check.
+    pub da_nodes_healthy: IntGauge,
+    /// Number of Celestia blocks processed.
+    pub blocks_processed: IntCounter,
+    /// Number of rollup transactions successfully processed.
+    pub txs_processed: IntCounter,
+    /// Number of rollup transactions that failed to process.
+    pub txs_failed: IntCounter,
+    /// Number of blobs and transactions recorded as dead letters.
+    pub dead_letters: IntCounter,
+    /// Number of transactions waiting to be posted.
+    pub mempool_size: IntGauge,
+    /// Time taken to submit a batch to Celestia.
+    pub batch_post_latency: Histogram,
+    /// Number of failed batch submissions.
+    pub batch_post_failures: IntCounter,
+    /// Number of blob submissions awaiting inclusion.
+    pub submissions_in_flight: IntGauge,
+    /// Size of the blobs posted by this node.
+    pub blob_size: Histogram,
+    /// Gas used by the blob submissions of this node.
+    pub batch_gas_used: IntCounter,
+    /// Fees paid for blob submissions in utia, see [`crate::fees::gas_price`].
+    pub batch_fees: Counter,
+}
+
+impl Metrics {
+    pub fn new() -> Result<Self> {
+        let registry = Registry::new_custom(Some("shard".to_string()), None)?;
+
+        let da_height = IntGauge::new("da_height", "Last processed Celestia height")?;
+        let network_head = IntGauge::new("network_head", "Latest known Celestia height")?;
+        let da_nodes_healthy = IntGauge::new("da_nodes_healthy", "Healthy Celestia nodes")?;
+        let blocks_processed =
+            IntCounter::new("blocks_processed_total", "Celestia blocks processed")?;
+        let txs_processed =
+            IntCounter::new("transactions_processed_total", "Transactions processed")?;
+        let txs_failed = IntCounter::new(
+            "transactions_failed_total",
+            "Transactions that failed to process",
+        )?;
+        let dead_letters = IntCounter::new(
+            "dead_letters_total",
+            "Rejected blobs and transactions recorded as dead letters",
+        )?;
+        let mempool_size = IntGauge::new("mempool_size", "Transactions waiting to be posted")?;
+        let batch_post_latency = Histogram::with_opts(
+            HistogramOpts::new("batch_post_latency_seconds", "Time to submit a batch blob")
+                .buckets(vec![0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 40.0, 80.0]),
+        )?;
+        let batch_post_failures =
+            IntCounter::new("batch_post_failures_total", "Failed batch submissions")?;
+        let submissions_in_flight = IntGauge::new(
+            "blob_submissions_in_flight",
+            "Blob submissions awaiting inclusion",
+        )?;
+        let blob_size = Histogram::with_opts(
+            HistogramOpts::new("blob_size_bytes", "Size of posted blobs")
+                .buckets(prometheus::exponential_buckets(256.0, 4.0, 8)?),
+        )?;
+        let batch_gas_used =
+            IntCounter::new("batch_gas_used_total", "Gas used by blob submissions")?;
+        let batch_fees = Counter::new("batch_fees_utia_total", "Fees paid for blob submissions")?;
+
+        registry.register(Box::new(da_height.clone()))?;
+        registry.register(Box::new(network_head.clone()))?;
+        registry.register(Box::new(da_nodes_healthy.clone()))?;
+        registry.register(Box::new(blocks_processed.clone()))?;
+        registry.register(Box::new(txs_processed.clone()))?;
+        registry.register(Box::new(txs_failed.clone()))?;
+        registry.register(Box::new(dead_letters.clone()))?;
+        registry.register(Box::new(mempool_size.clone()))?;
+        registry.register(Box::new(batch_post_latency.clone()))?;
+        registry.register(Box::new(batch_post_failures.clone()))?;
+        registry.register(Box::new(submissions_in_flight.clone()))?;
+        registry.register(Box::new(blob_size.clone()))?;
+        registry.register(Box::new(batch_gas_used.clone()))?;
+        registry.register(Box::new(batch_fees.clone()))?;
+
+        Ok(Metrics {
+            registry,
+            da_height,
+            network_head,
+            da_nodes_healthy,
+            blocks_processed,
+            txs_processed,
+            txs_failed,
+            dead_letters,
+            mempool_size,
+            batch_post_latency,
+            batch_post_failures,
+            submissions_in_flight,
+            blob_size,
+            batch_gas_used,
+            batch_fees,
+        })
+    }
+
+    /// Encodes all metrics in the Prometheus text format.
+    pub fn encode(&self) -> Result<String> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
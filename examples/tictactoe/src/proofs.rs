@@ -0,0 +1,144 @@
+use anyhow::{anyhow, bail, Context, Result};
+use celestia_types::nmt::Namespace;
+use celestia_types::row_namespace_data::{NamespaceData, RowNamespaceDataId};
+use celestia_types::{AppVersion, Blob, ExtendedHeader};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::da;
+
+/// Height and hash of a Celestia header that is trusted without verification,
+/// obtained from a source independent of the Celestia nodes the rollup node
+/// reads from. Later headers are verified against it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrustedHeader {
+    pub height: u64,
+    /// Hex encoded header hash
+    pub hash: String,
+}
+
+impl TrustedHeader {
+    /// Checks that `header` is the trusted header.
+    pub fn check(&self, header: &ExtendedHeader) -> Result<()> {
+        header.validate().context("Invalid extended header")?;
+        let hash = header.hash().to_string();
+        if header.height().value() != self.height || !hash.eq_ignore_ascii_case(&self.hash) {
+            bail!(
+                "header {} at height {} is not the trusted header {}",
+                hash,
+                header.height().value(),
+                self
+            );
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TrustedHeader {
+    type Err = anyhow::Error;
+
+    /// Parses `HEIGHT:HASH`.
+    fn from_str(s: &str) -> Result<Self> {
+        let (height, hash) = s
+            .split_once(':')
+            .ok_or_else(|| anyhow!("expected HEIGHT:HASH, got {}", s))?;
+        let height = height.parse().context("Invalid trusted header height")?;
+        let bytes = hex::decode(hash).context("Invalid trusted header hash")?;
+        if bytes.len() != 32 {
+            bail!("trusted header hash must be 32 bytes, got {}", bytes.len());
+        }
+        Ok(TrustedHeader {
+            height,
+            hash: hash.to_string(),
+        })
+    }
+}
+
+impl Display for TrustedHeader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.height, self.hash)
+    }
+}
+
+/// Checks `blobs` against the blobs of `namespace` in the block of `header`,
+/// and returns the blobs reconstructed from the proven shares.
+///
+/// `data` holds the namespace shares of every row whose root covers the
+/// namespace, with a proof against that root. Rows with shares carry an
+/// inclusion proof, empty rows an absence proof. The returned blobs only
+/// depend on `header` and the proofs, and it is an error if `blobs` differ
+/// from them.
+pub fn verify_namespace(
+    header: &ExtendedHeader,
+    namespace: Namespace,
+    data: &NamespaceData,
+    blobs: &[Blob],
+) -> Result<Vec<Blob>> {
+    header.validate().context("Invalid extended header")?;
+    let height = header.height().value();
+
+    let rows: Vec<u16> = header
+        .dah
+        .row_roots()
+        .iter()
+        .enumerate()
+        .filter(|(_, root)| {
+            root.min_namespace() <= *namespace && *namespace <= root.max_namespace()
+        })
+        .map(|(index, _)| index as u16)
+        .collect();
+    if data.rows.len() != rows.len() {
+        bail!(
+            "expected namespace data for {} rows at height {}, got {}",
+            rows.len(),
+            height,
+            data.rows.len()
+        );
+    }
+
+    for (row_index, row) in rows.into_iter().zip(&data.rows) {
+        let id = RowNamespaceDataId::new(namespace, row_index, height)?;
+        row.verify(id, &header.dah)
+            .with_context(|| format!("Invalid namespace proof for row {}", row_index))?;
+    }
+
+    let app_version = AppVersion::from_u64(header.header.version.app)
+        .context("Unknown app version in extended header")?;
+    let proven = Blob::reconstruct_all(data.rows.iter().flat_map(|row| &row.shares), app_version)
+        .context("Failed to reconstruct blobs from proven shares")?;
+    if !da::same_blobs(blobs, &proven) {
+        bail!(
+            "blobs at height {} do not match the proven namespace data: got {}, proven {}",
+            height,
+            blobs.len(),
+            proven.len()
+        );
+    }
+    Ok(proven)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "6BE6D2B1D5C13BCB2E6A3A4B7D6D2C2F7C3A9A18C0D4B28B8E2E1F4F1A2B3C4D";
+
+    #[test]
+    fn parses_trusted_headers() {
+        let trusted: TrustedHeader = format!("1234:{}", HASH).parse().unwrap();
+        assert_eq!(trusted.height, 1234);
+        assert_eq!(trusted.hash, HASH);
+        assert_eq!(
+            trusted.to_string().parse::<TrustedHeader>().unwrap(),
+            trusted
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_trusted_headers() {
+        assert!(HASH.parse::<TrustedHeader>().is_err());
+        assert!(format!("x:{}", HASH).parse::<TrustedHeader>().is_err());
+        assert!("1234:abcd".parse::<TrustedHeader>().is_err());
+        assert!("1234:not hex".parse::<TrustedHeader>().is_err());
+    }
+}
@@ -0,0 +1,434 @@
+use anyhow::{anyhow, bail, Result};
+use celestia_types::Blob;
+use prism_common::keys::{Signature, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::deadletter::{BlobRef, DeadLetter};
+use crate::envelope::BlobPayload;
+use crate::forks::{self, ProtocolVersion};
+use crate::tx::Transaction;
+
+/// Hash of a [`SignedBatch`], used to chain batches together.
+pub type BatchHash = [u8; 32];
+
+/// Number of executed batch hashes kept to tell resubmitted batches from
+/// superseded ones.
+const EXECUTED_BATCH_WINDOW: usize = 1000;
+
+/// A batch posted by the centralized sequencer. Batches are numbered and
+/// reference the hash of the previous batch, so they are executed in the
+/// order the sequencer intended.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct SignedBatch {
+    pub batch_number: u64,
+    /// Hash of the previous batch, all zeroes for the first batch.
+    pub prev_batch_hash: BatchHash,
+    pub transactions: Vec<Transaction>,
+    /// Signature of bincode::serialize(&(batch_number, prev_batch_hash,
+    /// transactions)) by the sequencer key.
+    pub signature: Signature,
+}
+
+impl SignedBatch {
+    pub fn new(
+        batch_number: u64,
+        prev_batch_hash: BatchHash,
+        transactions: Vec<Transaction>,
+        key: &SigningKey,
+    ) -> Result<Self> {
+        let mut batch = SignedBatch {
+            batch_number,
+            prev_batch_hash,
+            transactions,
+            signature: Signature::default(),
+        };
+        batch.signature = key.sign(&batch.signature_msg()?);
+        Ok(batch)
+    }
+
+    pub fn verify(&self, sequencer: &VerifyingKey) -> Result<()> {
+        sequencer.verify_signature(&self.signature_msg()?, &self.signature)
+    }
+
+    pub fn hash(&self) -> BatchHash {
+        let encoded = bincode::serialize(self).expect("batches are always serializable");
+        Sha256::digest(encoded).into()
+    }
+
+    fn signature_msg(&self) -> Result<Vec<u8>> {
+        bincode::serialize(&(self.batch_number, self.prev_batch_hash, &self.transactions))
+            .map_err(|e| anyhow!(e))
+    }
+}
+
+/// Transactions to execute, together with the blob they were posted in.
+pub struct InboxBatch {
+    pub blob: BlobRef,
+    pub transactions: Vec<Transaction>,
+}
+
+/// Result of [`SequencerInbox::process_block`].
+#[derive(Default)]
+pub struct InboxOutput {
+    /// Batches to execute, in order
+    pub batches: Vec<InboxBatch>,
+    pub rejected: Vec<DeadLetter>,
+    /// Signed batches that will never be executed, because another batch
+    /// with the same number was executed instead
+    pub orphaned: Vec<SignedBatch>,
+}
+
+/// Decides which transactions are executed in sequencer mode, and in which
+/// order.
+///
+/// Batches signed by the sequencer are executed in batch number order. Batches
+/// that arrive early are held back until the missing batches arrive. If
+/// several batches with the same number arrive, e.g. because the sequencer
+/// restarted while batches were in flight, the first one extending the chain
+/// is executed and the others are orphaned. Any other blob is a
+/// forced-inclusion blob, executed `forced_inclusion_delay` DA blocks after it
+/// was included, so users can not be censored by the sequencer.
+pub struct SequencerInbox {
+    sequencer: VerifyingKey,
+    forced_inclusion_delay: u64,
+    next_batch_number: u64,
+    last_batch_hash: BatchHash,
+    /// Signed batches that arrived before their predecessors, in order of
+    /// arrival for each batch number
+    early_batches: BTreeMap<u64, Vec<(BlobRef, SignedBatch)>>,
+    /// Hashes of the most recently executed batches by batch number
+    executed: BTreeMap<u64, BatchHash>,
+    /// Forced-inclusion transactions with the DA height they were included at
+    forced: VecDeque<(u64, InboxBatch)>,
+}
+
+impl SequencerInbox {
+    pub fn new(sequencer: VerifyingKey, forced_inclusion_delay: u64) -> Self {
+        SequencerInbox {
+            sequencer,
+            forced_inclusion_delay,
+            next_batch_number: 0,
+            last_batch_hash: [0; 32],
+            early_batches: BTreeMap::new(),
+            executed: BTreeMap::new(),
+            forced: VecDeque::new(),
+        }
+    }
+
+    /// Returns the number of the next batch to execute and the hash of the
+    /// last executed batch.
+    pub fn head(&self) -> (u64, BatchHash) {
+        (self.next_batch_number, self.last_batch_hash)
+    }
+
+    /// Takes the blobs of the DA block at `height`, decoded according to
+    /// `version`, and returns the transactions to execute for it, in order:
+    /// first the sequenced batches that are next in line, then
+    /// forced-inclusion transactions whose delay has passed. Rejected blobs
+    /// are returned as dead letters.
+    pub fn process_block(
+        &mut self,
+        height: u64,
+        version: ProtocolVersion,
+        blobs: &[Blob],
+    ) -> InboxOutput {
+        let mut output = InboxOutput::default();
+        for (blob_index, blob) in blobs.iter().enumerate() {
+            let blob_ref = BlobRef::new(height, blob_index, blob);
+            match forks::decode_blob(version, blob) {
+                Ok(BlobPayload::Signed(batch)) if batch.verify(&self.sequencer).is_ok() => {
+                    if batch.batch_number < self.next_batch_number {
+                        self.drop_executed_number(blob_ref, batch, &mut output);
+                    } else {
+                        self.early_batches
+                            .entry(batch.batch_number)
+                            .or_default()
+                            .push((blob_ref, batch));
+                    }
+                }
+                Ok(payload) => self.forced.push_back((
+                    height,
+                    InboxBatch {
+                        blob: blob_ref,
+                        transactions: payload.into_transactions(),
+                    },
+                )),
+                Err(e) => output.rejected.push(DeadLetter::blob(
+                    blob_ref,
+                    format!("undecodable blob: {:#}", e),
+                )),
+            }
+        }
+
+        while let Some(candidates) = self.early_batches.remove(&self.next_batch_number) {
+            let mut candidates = candidates.into_iter();
+            let Some((blob, batch)) =
+                candidates.find_map(|(blob, batch)| match self.check_chain(&batch) {
+                    Ok(()) => Some((blob, batch)),
+                    Err(e) => {
+                        output.rejected.push(DeadLetter::blob(
+                            blob,
+                            format!("signed batch {}: {}", batch.batch_number, e),
+                        ));
+                        output.orphaned.push(batch);
+                        None
+                    }
+                })
+            else {
+                continue;
+            };
+
+            debug!("executing sequenced batch {}", batch.batch_number);
+            let batch_hash = batch.hash();
+            self.executed.insert(batch.batch_number, batch_hash);
+            if self.executed.len() > EXECUTED_BATCH_WINDOW {
+                self.executed.pop_first();
+            }
+            self.next_batch_number += 1;
+            self.last_batch_hash = batch_hash;
+            output.batches.push(InboxBatch {
+                blob,
+                transactions: batch.transactions,
+            });
+            // the remaining candidates were signed with the same number
+            for (blob, batch) in candidates {
+                self.drop_executed_number(blob, batch, &mut output);
+            }
+        }
+
+        while let Some((included_at, _)) = self.forced.front() {
+            if included_at + self.forced_inclusion_delay > height {
+                break;
+            }
+            let (included_at, batch) = self.forced.pop_front().unwrap();
+            debug!(
+                "executing forced-inclusion blob from height {}",
+                included_at
+            );
+            output.batches.push(batch);
+        }
+
+        output
+    }
+
+    /// Handles a batch whose number was already executed. Resubmissions of
+    /// the executed batch are expected and dropped, any other batch is
+    /// orphaned.
+    fn drop_executed_number(&self, blob: BlobRef, batch: SignedBatch, output: &mut InboxOutput) {
+        match self.executed.get(&batch.batch_number) {
+            Some(executed) if *executed != batch.hash() => {
+                output.rejected.push(DeadLetter::blob(
+                    blob,
+                    format!(
+                        "signed batch {} was superseded by another batch with the same number",
+                        batch.batch_number
+                    ),
+                ));
+                output.orphaned.push(batch);
+            }
+            _ => debug!("dropping already executed batch {}", batch.batch_number),
+        }
+    }
+
+    fn check_chain(&self, batch: &SignedBatch) -> Result<()> {
+        if batch.prev_batch_hash != self.last_batch_hash {
+            bail!(
+                "previous batch hash {} does not match {}",
+                hex::encode(batch.prev_batch_hash),
+                hex::encode(self.last_batch_hash)
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::envelope::{self, Compression, PayloadKind};
+    use crate::node::APP_VERSION;
+    use crate::testing;
+    use crate::tx::Batch;
+    use celestia_types::nmt::Namespace;
+
+    const DELAY: u64 = 5;
+
+    fn keypair() -> (SigningKey, VerifyingKey) {
+        let key = keystore_rs::create_signing_key();
+        let vk = VerifyingKey::Ed25519(key.verification_key());
+        (SigningKey::Ed25519(Box::new(key)), vk)
+    }
+
+    fn namespace() -> Namespace {
+        Namespace::new_v0(&[1, 2, 3, 4]).unwrap()
+    }
+
+    fn signed_blob(batch: &SignedBatch) -> Blob {
+        let data = envelope::seal(
+            PayloadKind::SignedBatch,
+            &bincode::serialize(batch).unwrap(),
+            Compression::None,
+        )
+        .unwrap();
+        Blob::new(namespace(), data, APP_VERSION).unwrap()
+    }
+
+    fn unsigned_blob() -> Blob {
+        let data = envelope::seal(
+            PayloadKind::Batch,
+            &bincode::serialize(&Batch::new(Vec::new())).unwrap(),
+            Compression::None,
+        )
+        .unwrap();
+        Blob::new(namespace(), data, APP_VERSION).unwrap()
+    }
+
+    /// Signs `count` consecutive empty batches, starting at batch 0.
+    fn chain(key: &SigningKey, count: u64) -> Vec<SignedBatch> {
+        let mut prev_batch_hash = [0; 32];
+        (0..count)
+            .map(|batch_number| {
+                let batch =
+                    SignedBatch::new(batch_number, prev_batch_hash, Vec::new(), key).unwrap();
+                prev_batch_hash = batch.hash();
+                batch
+            })
+            .collect()
+    }
+
+    fn orphaned_nonces(output: &InboxOutput) -> Vec<u64> {
+        output
+            .orphaned
+            .iter()
+            .flat_map(|batch| batch.transactions.iter().map(|tx| tx.nonce))
+            .collect()
+    }
+
+    fn positions(batches: &[InboxBatch]) -> Vec<(u64, usize)> {
+        batches
+            .iter()
+            .map(|batch| (batch.blob.da_height, batch.blob.blob_index))
+            .collect()
+    }
+
+    #[test]
+    fn executes_signed_batches_in_batch_number_order() {
+        let (key, vk) = keypair();
+        let mut inbox = SequencerInbox::new(vk, DELAY);
+        let batches = chain(&key, 3);
+
+        let output =
+            inbox.process_block(1, 0, &[signed_blob(&batches[2]), signed_blob(&batches[1])]);
+        assert!(output.batches.is_empty());
+        assert!(output.rejected.is_empty());
+
+        let output = inbox.process_block(2, 0, &[signed_blob(&batches[0])]);
+        assert_eq!(positions(&output.batches), [(2, 0), (1, 1), (1, 0)]);
+        assert!(output.rejected.is_empty());
+        assert_eq!(inbox.head(), (3, batches[2].hash()));
+    }
+
+    #[test]
+    fn drops_already_executed_batches() {
+        let (key, vk) = keypair();
+        let mut inbox = SequencerInbox::new(vk, DELAY);
+        let batches = chain(&key, 1);
+
+        let output = inbox.process_block(1, 0, &[signed_blob(&batches[0])]);
+        assert_eq!(output.batches.len(), 1);
+        let output = inbox.process_block(2, 0, &[signed_blob(&batches[0])]);
+        assert!(output.batches.is_empty());
+        assert!(output.rejected.is_empty());
+        assert!(output.orphaned.is_empty());
+    }
+
+    #[test]
+    fn rejects_batches_that_do_not_extend_the_chain() {
+        let (key, vk) = keypair();
+        let mut inbox = SequencerInbox::new(vk, DELAY);
+        let forked = SignedBatch::new(0, [1; 32], Vec::new(), &key).unwrap();
+
+        let output = inbox.process_block(1, 0, &[signed_blob(&forked)]);
+        assert!(output.batches.is_empty());
+        assert_eq!(output.rejected.len(), 1);
+        assert_eq!(output.orphaned.len(), 1);
+        assert_eq!(inbox.head(), (0, [0; 32]));
+    }
+
+    #[test]
+    fn delays_forced_inclusion_blobs() {
+        let (key, vk) = keypair();
+        let mut inbox = SequencerInbox::new(vk, DELAY);
+        let batches = chain(&key, 2);
+
+        let output = inbox.process_block(10, 0, &[unsigned_blob(), signed_blob(&batches[0])]);
+        assert_eq!(positions(&output.batches), [(10, 1)]);
+
+        let output = inbox.process_block(10 + DELAY - 1, 0, &[]);
+        assert!(output.batches.is_empty());
+
+        // sequenced batches go first, then forced-inclusion blobs
+        let output = inbox.process_block(10 + DELAY, 0, &[signed_blob(&batches[1])]);
+        assert_eq!(positions(&output.batches), [(10 + DELAY, 0), (10, 0)]);
+    }
+
+    #[test]
+    fn treats_batches_of_other_signers_as_forced_inclusion() {
+        let (key, vk) = keypair();
+        let mut inbox = SequencerInbox::new(vk, DELAY);
+        let other = chain(&keypair().0, 1);
+
+        let output = inbox.process_block(1, 0, &[signed_blob(&other[0])]);
+        assert!(output.batches.is_empty());
+        let output = inbox.process_block(1 + DELAY, 0, &[]);
+        assert_eq!(positions(&output.batches), [(1, 0)]);
+        assert_eq!(inbox.head(), (0, [0; 32]));
+    }
+
+    #[test]
+    fn orphans_batches_replaced_after_a_restart() {
+        let (key, vk) = keypair();
+        let mut inbox = SequencerInbox::new(vk, DELAY);
+        let txs = testing::transactions(4);
+        let old_0 = SignedBatch::new(0, [0; 32], vec![txs[0].clone()], &key).unwrap();
+        let old_1 = SignedBatch::new(1, old_0.hash(), vec![txs[1].clone()], &key).unwrap();
+
+        // batch 0 was not included before the sequencer restarted, batch 1 was
+        let output = inbox.process_block(1, 0, &[signed_blob(&old_1)]);
+        assert!(output.batches.is_empty());
+
+        // after the restart, the sequencer signs batch 0 and 1 again
+        let new_0 = SignedBatch::new(0, [0; 32], vec![txs[2].clone()], &key).unwrap();
+        let new_1 = SignedBatch::new(1, new_0.hash(), vec![txs[3].clone()], &key).unwrap();
+        let output = inbox.process_block(2, 0, &[signed_blob(&new_0)]);
+        assert_eq!(positions(&output.batches), [(2, 0)]);
+        assert_eq!(output.rejected.len(), 1);
+        assert_eq!(orphaned_nonces(&output), [1]);
+
+        let output = inbox.process_block(3, 0, &[signed_blob(&new_1)]);
+        assert_eq!(positions(&output.batches), [(3, 0)]);
+        assert!(output.orphaned.is_empty());
+
+        // the old batch 0 is included late
+        let output = inbox.process_block(4, 0, &[signed_blob(&old_0), signed_blob(&new_0)]);
+        assert!(output.batches.is_empty());
+        assert_eq!(output.rejected.len(), 1);
+        assert_eq!(orphaned_nonces(&output), [0]);
+        assert_eq!(inbox.head(), (2, new_1.hash()));
+    }
+
+    #[test]
+    fn executes_the_first_candidate_extending_the_chain() {
+        let (key, vk) = keypair();
+        let mut inbox = SequencerInbox::new(vk, DELAY);
+        let forked = SignedBatch::new(0, [1; 32], Vec::new(), &key).unwrap();
+        let batches = chain(&key, 1);
+
+        let output = inbox.process_block(1, 0, &[signed_blob(&forked), signed_blob(&batches[0])]);
+        assert_eq!(positions(&output.batches), [(1, 1)]);
+        assert_eq!(output.orphaned.len(), 1);
+        assert_eq!(inbox.head(), (1, batches[0].hash()));
+    }
+}
@@ -1,12 +1,15 @@
+use crate::address::Address;
+use crate::forks::ProtocolVersion;
 use crate::tx::{Transaction, TransactionType};
 use anyhow::anyhow;
 use anyhow::Result;
 use prism_common::keys::VerifyingKey;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::fmt::Formatter;
 
-#[derive(Clone, Hash)]
+#[derive(Clone, Hash, Serialize, Deserialize)]
 pub struct Board {
     pub creator: VerifyingKey,
     pub player: Option<VerifyingKey>,
@@ -88,15 +91,18 @@ impl Display for Board {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct State {
     pub games: HashMap<String, Board>,
+    /// Protocol version of the block being processed, see [`crate::forks`].
+    pub protocol_version: ProtocolVersion,
 }
 
 impl State {
     pub fn new() -> Self {
         State {
             games: HashMap::new(),
+            protocol_version: 0,
         }
     }
     /// Validates a transaction against the current chain state.
@@ -160,20 +166,27 @@ impl State {
             TransactionType::Move { game_id, position } => {
                 let board = self.games.get_mut(&game_id).unwrap();
                 board.state[position as usize] = if board.turn % 2 == 0 { 1 } else { 2 };
-                println!("{}: \n{}", game_id, board);
-                if board.winner().is_some() {
-                    println!("Game has been won!");
+                info!(
+                    "{} (move by {}): \n{}",
+                    game_id,
+                    Address::from(&tx.vk),
+                    board
+                );
+                if let Some(winner) = board.winner() {
+                    info!("Game has been won by {}!", Address::from(&winner));
                 } else if board.is_full() {
-                    println!("Game is a draw!");
+                    info!("Game is a draw!");
                 }
                 board.turn += 1;
                 Ok(())
             }
             TransactionType::JoinGame { game_id } => {
+                info!("{} joined game {}", Address::from(&tx.vk), game_id);
                 self.games.get_mut(&game_id).unwrap().player = Some(tx.vk);
                 Ok(())
             }
             TransactionType::CreateGame { game_id } => {
+                info!("{} created game {}", Address::from(&tx.vk), game_id);
                 self.games.insert(
                     game_id,
                     Board {
@@ -187,4 +200,9 @@ impl State {
             }
         }
     }
+    /// Called by the node before processing the transactions of each DA
+    /// block.
+    pub(crate) fn begin_block(&mut self, protocol_version: ProtocolVersion) {
+        self.protocol_version = protocol_version;
+    }
 }
@@ -0,0 +1,24 @@
+use prism_common::keys::{Signature, VerifyingKey};
+
+use crate::tx::{Transaction, TransactionType};
+
+/// Returns a transaction of the first [`TransactionType`] with all its fields
+/// zeroed, so the tests do not depend on the transaction types of the rollup.
+/// Each transaction gets a new key.
+pub fn transaction(nonce: u64) -> Transaction {
+    // bincode reads the variant index and every field from the zero bytes
+    let tx_type: TransactionType = bincode::deserialize(&[0; 1024])
+        .expect("the first transaction type can not be decoded from zero bytes");
+    Transaction {
+        signature: Signature::default(),
+        vk: VerifyingKey::Ed25519(keystore_rs::create_signing_key().verification_key()),
+        nonce,
+        tx_type,
+        blob_signer: None,
+    }
+}
+
+/// Returns `count` transactions with the nonces `0..count`.
+pub fn transactions(count: u64) -> Vec<Transaction> {
+    (0..count).map(transaction).collect()
+}
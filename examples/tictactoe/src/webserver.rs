@@ -1,13 +1,165 @@
-use crate::node::Node;
+use crate::address::Address;
+use crate::deadletter::DeadLetter;
+use crate::node::{Node, NodeStatus, QueueError};
 use crate::tx::Transaction;
-use axum::{extract::State as AxumState, http::StatusCode, Json};
+use axum::extract::{Query, State as AxumState};
+use axum::http::{header, Request};
+use axum::response::{IntoResponse, Response};
+use axum::{http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use tracing::Span;
+
+#[derive(Serialize)]
+pub(crate) struct SubmitTxResponse {
+    /// Hash of the submitted transaction.
+    pub tx_hash: String,
+    /// Address of the account that sent the transaction.
+    pub sender: Address,
+}
+
+/// Number of dead letters returned if the request sets no limit.
+const DEFAULT_DEAD_LETTER_LIMIT: usize = 100;
+const MAX_DEAD_LETTER_LIMIT: usize = 1_000;
+
+#[derive(Deserialize)]
+pub(crate) struct DeadLettersQuery {
+    /// Only return dead letters from this Celestia height on
+    #[serde(default)]
+    from_height: u64,
+    limit: Option<usize>,
+}
+
+/// Creates the span for an incoming request, tagged with the request id set
+/// by the `x-request-id` middleware.
+pub(crate) fn make_request_span<B>(request: &Request<B>) -> Span {
+    let request_id = request
+        .headers()
+        .get("x-request-id")
+        .and_then(|id| id.to_str().ok())
+        .unwrap_or_default();
+    info_span!(
+        "http",
+        method = %request.method(),
+        uri = %request.uri(),
+        request_id,
+    )
+}
 
 pub(crate) async fn submit_tx(
     AxumState(node): AxumState<Arc<Node>>,
     Json(tx): Json<Transaction>,
-) -> Result<(), (StatusCode, String)> {
+) -> Result<Json<SubmitTxResponse>, (StatusCode, String)> {
+    let tx_hash = tx.hash();
+    let sender = tx.sender();
     node.queue_transaction(tx)
         .await
+        .map_err(queue_error_response)?;
+    Ok(Json(SubmitTxResponse { tx_hash, sender }))
+}
+
+/// Maps the reason a transaction was rejected to the response status.
+fn queue_error_response(e: QueueError) -> (StatusCode, String) {
+    let status = match &e {
+        QueueError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+        // not 503, which followers treat as an unavailable upstream and retry
+        QueueError::Congested => StatusCode::TOO_MANY_REQUESTS,
+        QueueError::Duplicate => StatusCode::CONFLICT,
+        // the same status a follower returns for transactions it forwards
+        QueueError::Invalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+    };
+    (status, e.to_string())
+}
+
+pub(crate) async fn metrics(
+    AxumState(node): AxumState<Arc<Node>>,
+) -> Result<String, (StatusCode, String)> {
+    node.metrics
+        .encode()
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
 }
+
+/// Validates a transaction submitted to a follower node and forwards it to an
+/// upstream sequencer. The upstream's response is returned unchanged.
+pub(crate) async fn forward_tx(
+    AxumState(node): AxumState<Arc<Node>>,
+    Json(tx): Json<Transaction>,
+) -> Result<Response, (StatusCode, String)> {
+    node.validate_transaction(&tx)
+        .await
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+
+    let forwarder = node.forwarder.as_ref().ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        "no upstream sequencer configured".to_string(),
+    ))?;
+    let upstream = forwarder
+        .forward(&tx)
+        .await
+        .map_err(|e| (StatusCode::BAD_GATEWAY, format!("{:#}", e)))?;
+
+    let status = StatusCode::from_u16(upstream.status).unwrap_or(StatusCode::BAD_GATEWAY);
+    let mut response = (status, upstream.body).into_response();
+    if let Some(content_type) = upstream
+        .content_type
+        .and_then(|content_type| content_type.parse().ok())
+    {
+        response
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, content_type);
+    }
+    Ok(response)
+}
+
+/// Receives a transaction gossiped by a peer.
+pub(crate) async fn gossip_tx(
+    AxumState(node): AxumState<Arc<Node>>,
+    Json(tx): Json<Transaction>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    match node.receive_gossiped_transaction(tx).await {
+        Ok(true) => Ok(StatusCode::ACCEPTED),
+        Ok(false) => Ok(StatusCode::OK),
+        Err(e) => Err((StatusCode::UNPROCESSABLE_ENTITY, e.to_string())),
+    }
+}
+
+/// Liveness probe, succeeds as long as the process is serving requests.
+pub(crate) async fn health() -> &'static str {
+    "ok"
+}
+
+/// Readiness probe, succeeds once historical sync has completed and the
+/// Celestia node is reachable.
+pub(crate) async fn ready(
+    AxumState(node): AxumState<Arc<Node>>,
+) -> Result<&'static str, (StatusCode, String)> {
+    if !node.is_synced() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "historical sync in progress".to_string(),
+        ));
+    }
+    if !node.is_da_connected() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "celestia node unreachable".to_string(),
+        ));
+    }
+    Ok("ready")
+}
+
+pub(crate) async fn status(AxumState(node): AxumState<Arc<Node>>) -> Json<NodeStatus> {
+    Json(node.status().await)
+}
+
+/// Lists blobs and transactions rejected during sync, oldest first.
+pub(crate) async fn dead_letters(
+    AxumState(node): AxumState<Arc<Node>>,
+    Query(query): Query<DeadLettersQuery>,
+) -> Json<Vec<DeadLetter>> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_DEAD_LETTER_LIMIT)
+        .min(MAX_DEAD_LETTER_LIMIT);
+    Json(node.dead_letters(query.from_height, limit).await)
+}
@@ -33,17 +33,141 @@ pub fn create_transaction(
 
     let tx_path = path.join("src").join("tx.rs");
     let state_path = path.join("src").join("state.rs");
+    let layout_path = path.join(LAYOUT_FILE);
 
+    // compare against the layout recorded by the last run, so manual edits
+    // to tx.rs since then are caught as well
+    let old_layout = match read_layout(&layout_path)? {
+        Some(layout) => layout,
+        None => transaction_type_layout(&parse_file(&fs::read_to_string(&tx_path)?)?),
+    };
     let tx_content = modify_tx_file(tx_name, &fields)?;
     let state_content = modify_state_file(tx_name, &fields)?;
+    let new_layout = transaction_type_layout(&parse_file(&tx_content)?);
 
     fs::write(tx_path, tx_content)?;
     fs::write(state_path, state_content)?;
+    fs::write(layout_path, format_layout(&new_layout))?;
 
     print_transaction_info(tx_name, &fields);
+    print_wire_compatibility_warning(&old_layout, &new_layout);
     Ok(())
 }
 
+// file in the project root recording the TransactionType layout, meant to be
+// committed along with src/tx.rs
+const LAYOUT_FILE: &str = "transaction_layout.txt";
+
+const LAYOUT_HEADER: &str = "\
+# Wire layout of TransactionType, one variant per line, written by `shard create-tx`.
+# Commit this file: create-tx compares against it to detect incompatible changes.
+";
+
+fn format_layout(layout: &[(String, String)]) -> String {
+    let mut content = LAYOUT_HEADER.to_string();
+    for (_, tokens) in layout {
+        content.push_str(tokens);
+        content.push('\n');
+    }
+    content
+}
+
+fn parse_layout(content: &str) -> Vec<(String, String)> {
+    content
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let name = line.split(' ').next().unwrap_or(line);
+            (name.to_string(), line.to_string())
+        })
+        .collect()
+}
+
+fn read_layout(path: &Path) -> Result<Option<Vec<(String, String)>>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(parse_layout(&fs::read_to_string(path)?)))
+}
+
+// returns the name and encoding-relevant tokens of each TransactionType
+// variant, in declaration order (bincode encodes variants by index)
+fn transaction_type_layout(ast: &syn::File) -> Vec<(String, String)> {
+    ast.items
+        .iter()
+        .find_map(|item| match item {
+            Item::Enum(item_enum) if item_enum.ident == "TransactionType" => Some(
+                item_enum
+                    .variants
+                    .iter()
+                    .map(|variant| {
+                        let mut variant = variant.clone();
+                        // attributes like docs or clap parsers don't change the encoding
+                        variant.attrs.clear();
+                        variant
+                            .fields
+                            .iter_mut()
+                            .for_each(|field| field.attrs.clear());
+                        // neither does a trailing comma
+                        match &mut variant.fields {
+                            Fields::Named(fields) => {
+                                fields.named = fields.named.clone().into_iter().collect()
+                            }
+                            Fields::Unnamed(fields) => {
+                                fields.unnamed = fields.unnamed.clone().into_iter().collect()
+                            }
+                            Fields::Unit => {}
+                        }
+                        (
+                            variant.ident.to_string(),
+                            variant.to_token_stream().to_string(),
+                        )
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+// existing transactions stay decodable as long as all previous variants keep
+// their index and fields
+fn wire_compatibility_changes(
+    old_layout: &[(String, String)],
+    new_layout: &[(String, String)],
+) -> Vec<String> {
+    old_layout
+        .iter()
+        .enumerate()
+        .filter_map(|(index, (name, layout))| match new_layout.get(index) {
+            Some((_, new)) if new == layout => None,
+            Some((new_name, _)) if new_name == name => {
+                Some(format!("{} (index {}) has different fields", name, index))
+            }
+            Some((new_name, _)) => {
+                Some(format!("index {} was {}, is now {}", index, name, new_name))
+            }
+            None => Some(format!("{} (index {}) was removed", name, index)),
+        })
+        .collect()
+}
+
+fn print_wire_compatibility_warning(
+    old_layout: &[(String, String)],
+    new_layout: &[(String, String)],
+) {
+    let changes = wire_compatibility_changes(old_layout, new_layout);
+    if changes.is_empty() {
+        return;
+    }
+
+    println!("\n⚠️  This changes the wire format of existing transaction types:");
+    for change in changes {
+        println!("  {}", change);
+    }
+    println!("Blobs posted before this change can no longer be decoded. If your rollup already has transactions on Celestia, keep the old types for decoding them and add a fork in src/forks.rs.");
+}
+
 pub fn modify_tx_file(tx_name: &str, fields: &[TransactionField]) -> Result<String> {
     let mut ast = parse_file(&fs::read_to_string("src/tx.rs")?)?;
 
@@ -217,7 +341,106 @@ fn print_transaction_info(tx_name: &str, fields: &[TransactionField]) {
     println!("✨ Created new transaction type: {}", tx_name);
     println!("Transaction fields:");
     for field in fields {
-        println!("  {}: {}", field.name, field.field_type);
+        if field.is_account() {
+            println!(
+                "  {}: {} (accepts @signer-name)",
+                field.name, field.field_type
+            );
+        } else {
+            println!("  {}: {}", field.name, field.field_type);
+        }
     }
     println!("\nUpdate the verify and process methods in src/tx.rs and src/state.rs to add your custom logic!");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(source: &str) -> Vec<(String, String)> {
+        transaction_type_layout(&parse_file(source).unwrap())
+    }
+
+    #[test]
+    fn layout_lists_variants_in_order() {
+        let layout = layout(
+            r#"
+            #[derive(Subcommand)]
+            pub enum TransactionType {
+                /// Does nothing
+                Noop,
+                Invite {
+                    #[arg(value_parser = crate::address::parse_address_arg)]
+                    player: Address,
+                    game_id: String,
+                },
+            }
+            "#,
+        );
+
+        let names: Vec<&str> = layout.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["Noop", "Invite"]);
+        // docs and clap attributes don't change the encoding
+        assert_eq!(layout[0].1, "Noop");
+        assert_eq!(
+            layout[1].1,
+            "Invite { player : Address , game_id : String }"
+        );
+    }
+
+    #[test]
+    fn layout_is_empty_without_transaction_type() {
+        assert!(layout("pub struct State;").is_empty());
+    }
+
+    #[test]
+    fn appending_variants_is_compatible() {
+        let old = layout("enum TransactionType { A, B { x: u64 } }");
+        let new = layout("enum TransactionType { A, B { x: u64, }, C }");
+        assert!(wire_compatibility_changes(&old, &new).is_empty());
+    }
+
+    #[test]
+    fn removing_noop_is_reported() {
+        let old = layout("enum TransactionType { Noop }");
+        let new = layout("enum TransactionType { SendMessage { msg: String } }");
+        assert_eq!(
+            wire_compatibility_changes(&old, &new),
+            ["index 0 was Noop, is now SendMessage"]
+        );
+    }
+
+    #[test]
+    fn changed_reordered_and_removed_variants_are_reported() {
+        let old = layout("enum TransactionType { A { x: u64 }, B, C }");
+        let new = layout("enum TransactionType { A { x: u32 }, C }");
+        assert_eq!(
+            wire_compatibility_changes(&old, &new),
+            [
+                "A (index 0) has different fields",
+                "index 1 was B, is now C",
+                "C (index 2) was removed",
+            ]
+        );
+    }
+
+    #[test]
+    fn layout_file_round_trip() {
+        let layout = layout("enum TransactionType { Noop, Move { x: u8, y: u8 }, Pair(u64, u64) }");
+        let content = format_layout(&layout);
+        assert!(content.starts_with('#'));
+        assert_eq!(parse_layout(&content), layout);
+    }
+
+    #[test]
+    fn manual_edits_are_detected_against_the_layout_file() {
+        let recorded = parse_layout(&format_layout(&layout(
+            "enum TransactionType { Move { x: u8, y: u8 } }",
+        )));
+        let edited = layout("enum TransactionType { Move { x: u16, y: u16 }, Resign }");
+        assert_eq!(
+            wire_compatibility_changes(&recorded, &edited),
+            ["Move (index 0) has different fields"]
+        );
+    }
+}
@@ -14,7 +14,7 @@ pub fn create_project(project_name: &str) -> Result<()> {
     let src_dir = project_dir.join("src");
     fs::create_dir_all(&src_dir).context("Failed to create src directory")?;
 
-    write_template_files(&src_dir)?;
+    write_template_files(&src_dir, project_name)?;
 
     let cargo_content = templates::CARGO_TEMPLATE.replace("shard-template", project_name);
     fs::write(project_dir.join("Cargo.toml"), cargo_content)
@@ -29,17 +29,37 @@ pub fn create_project(project_name: &str) -> Result<()> {
     Ok(())
 }
 
-fn write_template_files(src_dir: &Path) -> Result<()> {
+fn write_template_files(src_dir: &Path, project_name: &str) -> Result<()> {
     let files = [
+        ("address.rs", templates::ADDRESS_RS),
+        ("admin.rs", templates::ADMIN_RS),
+        ("da.rs", templates::DA_RS),
+        ("deadletter.rs", templates::DEADLETTER_RS),
+        ("envelope.rs", templates::ENVELOPE_RS),
+        ("fees.rs", templates::FEES_RS),
+        ("forks.rs", templates::FORKS_RS),
+        ("forwarder.rs", templates::FORWARDER_RS),
+        ("gossip.rs", templates::GOSSIP_RS),
         ("lib.rs", templates::LIB_RS),
         ("main.rs", templates::MAIN_RS),
+        ("metrics.rs", templates::METRICS_RS),
         ("node.rs", templates::NODE_RS),
+        ("proofs.rs", templates::PROOFS_RS),
+        ("schedule.rs", templates::SCHEDULE_RS),
+        ("sequencer.rs", templates::SEQUENCER_RS),
         ("state.rs", templates::STATE_RS),
+        ("testing.rs", templates::TESTING_RS),
         ("tx.rs", templates::TX_RS),
         ("webserver.rs", templates::SERVER_RS),
     ];
 
+    // the binary uses the modules through the library crate
+    let crate_name = project_name.replace('-', "_");
     for (filename, content) in files {
+        let content = match filename {
+            "main.rs" => content.replace("shard_template", &crate_name),
+            _ => content.to_string(),
+        };
         fs::write(src_dir.join(filename), content)
             .with_context(|| format!("Failed to create {}", filename))?;
     }
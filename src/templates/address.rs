@@ -0,0 +1,216 @@
+use anyhow::{anyhow, Context, Result};
+use bech32::{Bech32m, Hrp};
+use keystore_rs::KeyStore;
+use prism_common::keys::VerifyingKey;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Human-readable prefix used when encoding addresses, e.g. `shard1...`.
+pub const ADDRESS_HRP: &str = "shard";
+
+/// Length of an address in bytes.
+pub const ADDRESS_LEN: usize = 20;
+
+/// Prefix marking a command line argument as the name of a local signer.
+pub const SIGNER_REF_PREFIX: char = '@';
+
+/// A short, human-readable account identifier derived from a [`VerifyingKey`].
+///
+/// The address is the first 20 bytes of the SHA-256 hash of the key, encoded
+/// as bech32m (which includes a checksum) with the [`ADDRESS_HRP`] prefix.
+/// Parsing also accepts the raw hex encoding, with or without a `0x` prefix.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Address([u8; ADDRESS_LEN]);
+
+impl Address {
+    pub fn new(bytes: [u8; ADDRESS_LEN]) -> Self {
+        Address(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; ADDRESS_LEN] {
+        &self.0
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+
+    fn from_slice(bytes: &[u8]) -> Result<Self> {
+        let bytes: [u8; ADDRESS_LEN] = bytes.try_into().map_err(|_| {
+            anyhow!(
+                "invalid address length: expected {} bytes, got {}",
+                ADDRESS_LEN,
+                bytes.len()
+            )
+        })?;
+        Ok(Address(bytes))
+    }
+}
+
+impl From<&VerifyingKey> for Address {
+    fn from(vk: &VerifyingKey) -> Self {
+        let hash = Sha256::digest(vk.as_bytes());
+        let mut bytes = [0u8; ADDRESS_LEN];
+        bytes.copy_from_slice(&hash[..ADDRESS_LEN]);
+        Address(bytes)
+    }
+}
+
+impl From<VerifyingKey> for Address {
+    fn from(vk: VerifyingKey) -> Self {
+        Address::from(&vk)
+    }
+}
+
+impl Display for Address {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let hrp = Hrp::parse(ADDRESS_HRP).map_err(|_| std::fmt::Error)?;
+        let encoded = bech32::encode::<Bech32m>(hrp, &self.0).map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", encoded)
+    }
+}
+
+impl std::fmt::Debug for Address {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Address({})", self)
+    }
+}
+
+impl FromStr for Address {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.starts_with(&format!("{}1", ADDRESS_HRP)) {
+            let (hrp, data) = bech32::decode(s).context("Invalid bech32 address")?;
+            if hrp.as_str() != ADDRESS_HRP {
+                return Err(anyhow!(
+                    "invalid address prefix: expected {}, got {}",
+                    ADDRESS_HRP,
+                    hrp
+                ));
+            }
+            return Address::from_slice(&data);
+        }
+
+        let hex_str = s.strip_prefix("0x").unwrap_or(s);
+        let bytes = hex::decode(hex_str).context("Address is neither bech32 nor hex")?;
+        Address::from_slice(&bytes)
+    }
+}
+
+impl Serialize for Address {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // JSON APIs get the readable form, bincode gets the raw bytes
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.to_string())
+        } else {
+            self.0.serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Address {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            Address::from_str(&s).map_err(serde::de::Error::custom)
+        } else {
+            let bytes = <[u8; ADDRESS_LEN]>::deserialize(deserializer)?;
+            Ok(Address(bytes))
+        }
+    }
+}
+
+/// Looks up a signer in the local keystore and returns its verifying key.
+pub fn resolve_signer(key_name: &str) -> Result<VerifyingKey> {
+    let signer = keystore_rs::KeyChain
+        .get_signing_key(key_name)
+        .map_err(|e| anyhow!("Failed to load signer '{}': {}", key_name, e))?;
+    Ok(signer.into())
+}
+
+/// Parses an [`Address`] from the command line. Accepts `@signer-name` to use
+/// the address of a local signer, or an encoded address.
+pub fn parse_address_arg(s: &str) -> Result<Address> {
+    match s.strip_prefix(SIGNER_REF_PREFIX) {
+        Some(key_name) => Ok(Address::from(&resolve_signer(key_name)?)),
+        None => Address::from_str(s),
+    }
+}
+
+/// Parses a [`VerifyingKey`] from the command line. Accepts `@signer-name` to
+/// use the key of a local signer, or a base64 encoded key.
+pub fn parse_verifying_key_arg(s: &str) -> Result<VerifyingKey> {
+    match s.strip_prefix(SIGNER_REF_PREFIX) {
+        Some(key_name) => resolve_signer(key_name),
+        None => VerifyingKey::try_from(s.to_string()).context("Invalid verifying key"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_key() -> VerifyingKey {
+        VerifyingKey::Ed25519(keystore_rs::create_signing_key().verification_key())
+    }
+
+    #[test]
+    fn bech32_round_trip() {
+        let address = Address::from(&test_key());
+        let encoded = address.to_string();
+        assert!(encoded.starts_with("shard1"));
+        assert_eq!(Address::from_str(&encoded).unwrap(), address);
+        assert_eq!(parse_address_arg(&encoded).unwrap(), address);
+    }
+
+    #[test]
+    fn hex_round_trip() {
+        let address = Address::from(&test_key());
+        assert_eq!(Address::from_str(&address.to_hex()).unwrap(), address);
+        assert_eq!(
+            Address::from_str(&format!("0x{}", address.to_hex())).unwrap(),
+            address
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_addresses() {
+        let encoded = Address::new([7; ADDRESS_LEN]).to_string();
+        // flip the last checksum character
+        let last = if encoded.ends_with('q') { "p" } else { "q" };
+        let corrupted = format!("{}{}", &encoded[..encoded.len() - 1], last);
+        assert!(Address::from_str(&corrupted).is_err());
+        assert!(Address::from_str("0x1234").is_err());
+        assert!(Address::from_str("not an address").is_err());
+    }
+
+    #[test]
+    fn serializes_readable_and_binary() {
+        let address = Address::new([7; ADDRESS_LEN]);
+        let json = serde_json::to_string(&address).unwrap();
+        assert_eq!(json, format!("\"{}\"", address));
+        assert_eq!(serde_json::from_str::<Address>(&json).unwrap(), address);
+
+        let encoded = bincode::serialize(&address).unwrap();
+        assert_eq!(encoded, [7; ADDRESS_LEN]);
+        assert_eq!(bincode::deserialize::<Address>(&encoded).unwrap(), address);
+    }
+
+    #[test]
+    fn signer_references_use_the_keystore() {
+        let err = parse_address_arg("@shard-test-missing-signer").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Failed to load signer 'shard-test-missing-signer'"));
+        assert!(parse_verifying_key_arg("@shard-test-missing-signer").is_err());
+    }
+
+    #[test]
+    fn verifying_key_arg_round_trip() {
+        let vk = test_key();
+        assert_eq!(parse_verifying_key_arg(&vk.to_string()).unwrap(), vk);
+    }
+}
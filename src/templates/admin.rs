@@ -0,0 +1,119 @@
+use crate::node::{MempoolEntry, Node};
+use axum::extract::{Path, State as AxumState};
+use axum::http::{header, Request, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::Response;
+use axum::routing::{delete, get, post, put};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Routes of the admin API. Every request must carry the configured admin
+/// token as `Authorization: Bearer <token>`.
+pub(crate) fn router(node: Arc<Node>) -> Router {
+    Router::new()
+        .route("/admin/batching/pause", post(pause_batching))
+        .route("/admin/batching/resume", post(resume_batching))
+        .route("/admin/batching/flush", post(flush_batch))
+        .route("/admin/batching/interval", put(set_batch_interval))
+        .route("/admin/mempool", get(list_mempool))
+        .route("/admin/mempool/:tx_hash", delete(drop_transaction))
+        .route("/admin/snapshot", post(snapshot_state))
+        .route_layer(middleware::from_fn_with_state(node.clone(), authenticate))
+        .with_state(node)
+}
+
+async fn authenticate<B>(
+    AxumState(node): AxumState<Arc<Node>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, StatusCode> {
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match (token, node.admin_token()) {
+        (Some(token), Some(expected)) if token == expected => Ok(next.run(request).await),
+        _ => Err(StatusCode::UNAUTHORIZED),
+    }
+}
+
+#[derive(Serialize)]
+struct FlushResponse {
+    tx_count: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BatchInterval {
+    /// The new batch interval (in seconds)
+    secs: u64,
+}
+
+#[derive(Serialize)]
+struct SnapshotResponse {
+    path: String,
+}
+
+async fn pause_batching(AxumState(node): AxumState<Arc<Node>>) -> StatusCode {
+    node.pause_batching();
+    StatusCode::NO_CONTENT
+}
+
+async fn resume_batching(AxumState(node): AxumState<Arc<Node>>) -> StatusCode {
+    node.resume_batching();
+    StatusCode::NO_CONTENT
+}
+
+async fn flush_batch(
+    AxumState(node): AxumState<Arc<Node>>,
+) -> Result<Json<FlushResponse>, (StatusCode, String)> {
+    node.flush_batch()
+        .await
+        .map(|tx_count| Json(FlushResponse { tx_count }))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+async fn set_batch_interval(
+    AxumState(node): AxumState<Arc<Node>>,
+    Json(interval): Json<BatchInterval>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if interval.secs == 0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "batch interval must be at least one second".to_string(),
+        ));
+    }
+    node.set_batch_interval(Duration::from_secs(interval.secs));
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_mempool(AxumState(node): AxumState<Arc<Node>>) -> Json<Vec<MempoolEntry>> {
+    Json(node.mempool().await)
+}
+
+async fn drop_transaction(
+    AxumState(node): AxumState<Arc<Node>>,
+    Path(tx_hash): Path<String>,
+) -> StatusCode {
+    if node.drop_transaction(&tx_hash).await {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+async fn snapshot_state(
+    AxumState(node): AxumState<Arc<Node>>,
+) -> Result<Json<SnapshotResponse>, (StatusCode, String)> {
+    node.snapshot_state()
+        .await
+        .map(|path| {
+            Json(SnapshotResponse {
+                path: path.display().to_string(),
+            })
+        })
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
@@ -0,0 +1,136 @@
+use celestia_types::TxConfig;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::node::Config;
+
+// Gas parameters of celestia-app for pay-for-blob transactions
+const GAS_PER_BLOB_BYTE: u64 = 8;
+const PFB_GAS_FIXED_COST: u64 = 75_000;
+const SHARE_SIZE: u64 = 512;
+/// Blob bytes in the first share, leaving room for the signer of
+/// share-version-1 blobs.
+const FIRST_SHARE_CONTENT_SIZE: u64 = 458;
+const CONTINUATION_SHARE_CONTENT_SIZE: u64 = 482;
+
+/// Headroom on top of the estimate, as the transaction bytes are charged too.
+const GAS_MARGIN: f64 = 1.1;
+
+/// Minimum gas price of celestia-app in utia. Celestia nodes pay at least
+/// this price if none is configured.
+pub const DEFAULT_GAS_PRICE: f64 = 0.002;
+
+/// How the gas limit of blob submissions is chosen if none is configured.
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum GasEstimation {
+    /// Let the Celestia node estimate the gas
+    #[default]
+    Node,
+    /// Compute the gas from the blob size, like celestia-app charges it
+    BlobSize,
+}
+
+/// Returns the gas needed to pay for a blob of `blob_size` bytes.
+pub fn estimate_blob_gas(blob_size: usize) -> u64 {
+    let blob_size = blob_size as u64;
+    let shares = if blob_size <= FIRST_SHARE_CONTENT_SIZE {
+        1
+    } else {
+        1 + (blob_size - FIRST_SHARE_CONTENT_SIZE).div_ceil(CONTINUATION_SHARE_CONTENT_SIZE)
+    };
+    let gas = shares * SHARE_SIZE * GAS_PER_BLOB_BYTE + PFB_GAS_FIXED_COST;
+    (gas as f64 * GAS_MARGIN) as u64
+}
+
+/// Builds the transaction config for submitting a blob of `blob_size` bytes.
+/// An explicit gas limit takes precedence over the estimation strategy.
+pub fn tx_config(cfg: &Config, blob_size: usize) -> TxConfig {
+    let gas = cfg.gas_limit.or(match cfg.gas_estimation {
+        GasEstimation::Node => None,
+        GasEstimation::BlobSize => Some(estimate_blob_gas(blob_size)),
+    });
+
+    TxConfig {
+        key_name: cfg.celestia_key_name.clone(),
+        gas_price: cfg.gas_price,
+        gas,
+        fee_granter_address: cfg.fee_granter.clone(),
+        ..TxConfig::default()
+    }
+}
+
+/// Returns the gas price of blob submissions in utia. If none is configured,
+/// the Celestia node picks the price and may raise it above
+/// [`DEFAULT_GAS_PRICE`] when the network is busy, so the returned price is
+/// a lower bound.
+pub fn gas_price(cfg: &Config) -> f64 {
+    cfg.gas_price.unwrap_or(DEFAULT_GAS_PRICE)
+}
+
+/// Returns the fee in utia paid for `gas_wanted` gas at `gas_price`.
+pub fn fee(gas_price: f64, gas_wanted: i64) -> f64 {
+    (gas_wanted as f64 * gas_price).ceil()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimates_gas_per_share() {
+        // one share
+        assert_eq!(estimate_blob_gas(1), 87_005);
+        assert_eq!(estimate_blob_gas(FIRST_SHARE_CONTENT_SIZE as usize), 87_005);
+        // two shares
+        assert_eq!(
+            estimate_blob_gas(FIRST_SHARE_CONTENT_SIZE as usize + 1),
+            91_511
+        );
+        let two_shares = FIRST_SHARE_CONTENT_SIZE + CONTINUATION_SHARE_CONTENT_SIZE;
+        assert_eq!(estimate_blob_gas(two_shares as usize), 91_511);
+        // three shares
+        assert_eq!(estimate_blob_gas(two_shares as usize + 1), 96_016);
+    }
+
+    #[test]
+    fn leaves_gas_to_the_celestia_node_by_default() {
+        let tx_config = tx_config(&Config::default(), 1_000);
+        assert_eq!(tx_config.gas, None);
+        assert_eq!(tx_config.gas_price, None);
+        assert_eq!(tx_config.key_name, None);
+        assert_eq!(tx_config.fee_granter_address, None);
+    }
+
+    #[test]
+    fn estimates_gas_from_the_blob_size() {
+        let cfg = Config {
+            gas_estimation: GasEstimation::BlobSize,
+            gas_price: Some(0.004),
+            celestia_key_name: Some("poster".to_string()),
+            ..Config::default()
+        };
+        let tx_config = tx_config(&cfg, 1_000);
+        assert_eq!(tx_config.gas, Some(estimate_blob_gas(1_000)));
+        assert_eq!(tx_config.gas_price, Some(0.004));
+        assert_eq!(tx_config.key_name.as_deref(), Some("poster"));
+    }
+
+    #[test]
+    fn prefers_the_configured_gas_limit() {
+        let cfg = Config {
+            gas_limit: Some(200_000),
+            gas_estimation: GasEstimation::BlobSize,
+            ..Config::default()
+        };
+        assert_eq!(tx_config(&cfg, 1_000).gas, Some(200_000));
+    }
+
+    #[test]
+    fn computes_fees_with_the_default_price() {
+        assert_eq!(gas_price(&Config::default()), DEFAULT_GAS_PRICE);
+        assert_eq!(fee(DEFAULT_GAS_PRICE, 100_000), 200.0);
+        // partial utia are rounded up
+        assert_eq!(fee(0.004, 100_001), 401.0);
+    }
+}
@@ -0,0 +1,138 @@
+use anyhow::Result;
+use async_lock::Mutex;
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::tx::Transaction;
+
+/// Number of transaction hashes remembered for deduplication.
+const SEEN_CAPACITY: usize = 100_000;
+
+/// Timeout for pushing a transaction to a single peer.
+const PEER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Set of recently seen transaction hashes, evicting the oldest hash once
+/// full.
+struct SeenSet {
+    hashes: HashSet<String>,
+    order: VecDeque<String>,
+    capacity: usize,
+}
+
+impl SeenSet {
+    fn new(capacity: usize) -> Self {
+        SeenSet {
+            hashes: HashSet::new(),
+            order: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    fn contains(&self, tx_hash: &str) -> bool {
+        self.hashes.contains(tx_hash)
+    }
+
+    /// Adds a hash to the set. Returns false if it was already present.
+    fn insert(&mut self, tx_hash: String) -> bool {
+        if !self.hashes.insert(tx_hash.clone()) {
+            return false;
+        }
+        self.order.push_back(tx_hash);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.hashes.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+/// Propagates validated transactions to the configured peers over HTTP.
+///
+/// Every node pushes transactions it has not seen before to all of its peers
+/// on `/gossip/tx`, and drops transactions it has already seen, so a
+/// transaction reaches every connected node exactly once.
+pub struct Gossip {
+    client: reqwest::Client,
+    peers: Vec<String>,
+    seen: Mutex<SeenSet>,
+}
+
+impl Gossip {
+    pub fn new(peers: Vec<String>) -> Result<Self> {
+        let client = reqwest::Client::builder().timeout(PEER_TIMEOUT).build()?;
+
+        Ok(Gossip {
+            client,
+            peers: peers
+                .into_iter()
+                .map(|url| url.trim_end_matches('/').to_string())
+                .collect(),
+            seen: Mutex::new(SeenSet::new(SEEN_CAPACITY)),
+        })
+    }
+
+    pub async fn has_seen(&self, tx_hash: &str) -> bool {
+        self.seen.lock().await.contains(tx_hash)
+    }
+
+    /// Marks a transaction as seen. Returns false if it was seen before.
+    pub async fn mark_seen(&self, tx_hash: String) -> bool {
+        self.seen.lock().await.insert(tx_hash)
+    }
+
+    /// Pushes a transaction to all peers in the background.
+    pub fn broadcast(self: &Arc<Self>, tx: Transaction) {
+        for peer in self.peers.clone() {
+            let gossip = self.clone();
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                if let Err(e) = gossip.push(&peer, &tx).await {
+                    warn!(tx_hash = %tx.hash(), "gossiping tx to {}: {}", peer, e);
+                }
+            });
+        }
+    }
+
+    async fn push(&self, peer: &str, tx: &Transaction) -> Result<()> {
+        self.client
+            .post(format!("{}/gossip/tx", peer))
+            .json(tx)
+            .send()
+            .await?
+            .error_for_status()?;
+        debug!(tx_hash = %tx.hash(), "gossiped tx to {}", peer);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seen_set_rejects_duplicates() {
+        let mut seen = SeenSet::new(2);
+        assert!(seen.insert("a".to_string()));
+        assert!(!seen.insert("a".to_string()));
+        assert!(seen.contains("a"));
+        assert!(!seen.contains("b"));
+    }
+
+    #[test]
+    fn seen_set_evicts_oldest_hash() {
+        let mut seen = SeenSet::new(2);
+        seen.insert("a".to_string());
+        seen.insert("b".to_string());
+        // a duplicate does not count towards the capacity
+        seen.insert("a".to_string());
+        seen.insert("c".to_string());
+
+        assert!(!seen.contains("a"));
+        assert!(seen.contains("b"));
+        assert!(seen.contains("c"));
+        assert!(seen.insert("a".to_string()));
+        assert!(!seen.contains("b"));
+    }
+}
@@ -1,7 +1,22 @@
+pub mod address;
+mod admin;
+pub mod da;
+pub mod deadletter;
+pub mod envelope;
+pub mod fees;
+pub mod forks;
+pub mod forwarder;
+pub mod gossip;
+pub mod metrics;
 pub mod node;
+pub mod proofs;
+pub mod schedule;
+pub mod sequencer;
 pub mod state;
+#[cfg(test)]
+mod testing;
 pub mod tx;
 mod webserver;
 
 #[macro_use]
-extern crate log;
+extern crate tracing;
@@ -1,46 +1,338 @@
 use anyhow::{Context, Result};
-use celestia_types::nmt::Namespace;
-use clap::{Parser, Subcommand};
+use celestia_rpc::{BlobClient, StateClient};
+use celestia_types::state::Address as CelestiaAddress;
+use celestia_types::{nmt::Namespace, Blob};
+use clap::{Parser, Subcommand, ValueEnum};
 use keystore_rs::KeyStore;
 use prism_common::keys::{Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use shard_template::address::{self, Address};
+use shard_template::da::DaClient;
+use shard_template::deadletter::DeadLetter;
+use shard_template::envelope::{self, Compression, PayloadKind};
+use shard_template::fees::{self, GasEstimation};
+use shard_template::node::{namespace_to_hex, Config, Node, NodeRole, APP_VERSION};
+use shard_template::tx::{Transaction, TransactionType, SIGNATURE_VERIFICATION_ENABLED};
+use std::fs;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
-use tx::{Transaction, TransactionType, SIGNATURE_VERIFICATION_ENABLED};
-
-mod node;
-mod state;
-mod tx;
-mod webserver;
-use node::{Config, Node};
+use tracing_subscriber::EnvFilter;
 
 #[macro_use]
-extern crate log;
+extern crate tracing;
 
-#[derive(Parser, Debug)]
+/// Default namespace of the rollup (hex encoded).
+const DEFAULT_NAMESPACE: &str = "2a2a2a2a";
+
+/// Placeholder printed instead of secrets by `print-config`.
+const REDACTED: &str = "<redacted>";
+
+/// Log filter used when neither `--log-filter` nor `RUST_LOG` is set.
+const DEFAULT_LOG_FILTER: &str = "info";
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum LogFormat {
+    /// Human readable log lines
+    Pretty,
+    /// One JSON object per log line, including span fields
+    Json,
+}
+
+/// Node configuration. Values are taken from command line flags, then
+/// environment variables, then the config file, then the defaults in
+/// [`Config::default`].
+#[derive(Parser, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 struct CommonArgs {
-    /// The namespace used by this rollup (hex encoded)
-    #[arg(long, default_value = "2a2a2a2a")]
-    namespace: String,
+    /// Path to a TOML config file
+    #[arg(long, env = "SHARD_CONFIG")]
+    #[serde(skip)]
+    config: Option<PathBuf>,
+
+    /// The namespace used by this rollup (hex encoded) [default: 2a2a2a2a]
+    #[arg(long, env = "SHARD_NAMESPACE")]
+    namespace: Option<String>,
+
+    /// Whether the node accepts and posts transactions [default: sequencer]
+    #[arg(long, value_enum, env = "SHARD_ROLE")]
+    role: Option<NodeRole>,
+
+    /// Upstream sequencer URLs that follower nodes forward transactions to
+    #[arg(long = "upstream", env = "SHARD_UPSTREAMS", value_delimiter = ',')]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    upstreams: Option<Vec<String>>,
+
+    /// How often followers retry all upstreams before giving up [default: 3]
+    #[arg(long, env = "SHARD_FORWARD_RETRIES")]
+    forward_retries: Option<u32>,
+
+    /// Peer node URLs to gossip transactions with
+    #[arg(long = "peer", env = "SHARD_PEERS", value_delimiter = ',')]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    peers: Option<Vec<String>>,
+
+    /// Ids of the sequencers taking turns posting batches
+    #[arg(long = "poster", env = "SHARD_POSTERS", value_delimiter = ',')]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    posters: Option<Vec<String>>,
 
-    /// The height from which to start syncing
-    #[arg(long, default_value_t = 1)]
-    start_height: u64,
+    /// The id of this node in the list of posters
+    #[arg(long, env = "SHARD_POSTER_ID")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    poster_id: Option<String>,
 
-    /// The URL of the Celestia node to connect to
-    #[arg(long, default_value = "ws://0.0.0.0:26658")]
-    celestia_url: String,
+    /// Number of DA blocks in each poster's slot [default: 10]
+    #[arg(long, env = "SHARD_SLOT_LENGTH")]
+    slot_length: Option<u64>,
 
-    /// The address to listen on for the node's webserver
-    #[arg(long, default_value = "0.0.0.0:3000")]
-    listen_addr: String,
+    /// DA blocks a transaction waits before a lagging poster's slot falls over [default: 5]
+    #[arg(long, env = "SHARD_FAILOVER_BLOCKS")]
+    failover_blocks: Option<u64>,
+
+    /// Key of the centralized sequencer (base64 or @signer-name), enables sequencer mode
+    #[arg(long, env = "SHARD_SEQUENCER_KEY")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sequencer_key: Option<String>,
+
+    /// Name of the keystore key to sign batches with, on the sequencer itself
+    #[arg(long, env = "SHARD_SEQUENCER_KEY_NAME")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sequencer_key_name: Option<String>,
+
+    /// DA blocks after which unsigned blobs are executed in sequencer mode [default: 50]
+    #[arg(long, env = "SHARD_FORCED_INCLUSION_DELAY")]
+    forced_inclusion_delay: Option<u64>,
+
+    /// The height from which to start syncing [default: 1]
+    #[arg(long, env = "SHARD_START_HEIGHT")]
+    start_height: Option<u64>,
+
+    /// URLs of the Celestia nodes to connect to, in failover order [default: ws://0.0.0.0:26658]
+    #[arg(
+        long = "celestia-url",
+        env = "SHARD_CELESTIA_URLS",
+        value_delimiter = ','
+    )]
+    celestia_urls: Option<Vec<String>>,
+
+    /// The address to listen on for the node's webserver [default: 0.0.0.0:3000]
+    #[arg(long, env = "SHARD_LISTEN_ADDR")]
+    listen_addr: Option<String>,
 
     /// The auth token to use when connecting to Celestia
-    #[arg(long)]
+    #[arg(long, env = "SHARD_AUTH_TOKEN", hide_env_values = true)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     auth_token: Option<String>,
 
-    /// The interval at which to post batches of transactions (in seconds)
-    #[arg(long, default_value_t = 3)]
-    batch_interval: u64,
+    /// Compare the blobs of each height with a second Celestia node [default: false]
+    #[arg(long, env = "SHARD_CROSS_CHECK_READS", num_args = 0..=1, default_missing_value = "true")]
+    cross_check_reads: Option<bool>,
+
+    /// Verify the blobs of each height with namespace proofs against its header [default: false]
+    #[arg(long, env = "SHARD_VERIFY_PROOFS", num_args = 0..=1, default_missing_value = "true")]
+    verify_proofs: Option<bool>,
+
+    /// Header to verify Celestia headers against with --verify-proofs, as HEIGHT:HASH
+    #[arg(long, env = "SHARD_TRUSTED_HEADER")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trusted_header: Option<String>,
+
+    /// Gas price for blob submissions (in utia) [default: estimated by the Celestia node]
+    #[arg(long, env = "SHARD_GAS_PRICE")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gas_price: Option<f64>,
+
+    /// Gas limit for blob submissions, overrides --gas-estimation
+    #[arg(long, env = "SHARD_GAS_LIMIT")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gas_limit: Option<u64>,
+
+    /// How the gas limit is chosen if --gas-limit is unset [default: node]
+    #[arg(long, value_enum, env = "SHARD_GAS_ESTIMATION")]
+    gas_estimation: Option<GasEstimation>,
+
+    /// Account paying the fees of blob submissions through a fee grant
+    #[arg(long, env = "SHARD_FEE_GRANTER")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fee_granter: Option<String>,
+
+    /// Key in the Celestia node's keyring to submit blobs with
+    #[arg(long, env = "SHARD_CELESTIA_KEY_NAME")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    celestia_key_name: Option<String>,
+
+    /// Post batches and `submit-tx --direct` blobs as share-version-1 blobs signed by the Celestia account [default: false]
+    #[arg(long, env = "SHARD_SIGNED_BLOBS", num_args = 0..=1, default_missing_value = "true")]
+    signed_blobs: Option<bool>,
+
+    /// How batches are compressed before posting [default: zstd]
+    #[arg(long, value_enum, env = "SHARD_COMPRESSION")]
+    compression: Option<Compression>,
+
+    /// Maximum size of a single blob (in bytes) [default: 1500000]
+    #[arg(long, env = "SHARD_MAX_BLOB_SIZE")]
+    max_blob_size: Option<usize>,
+
+    /// Maximum number of transactions in a single blob [default: 1000]
+    #[arg(long, env = "SHARD_MAX_TXS_PER_BLOB")]
+    max_txs_per_blob: Option<usize>,
+
+    /// The longest a transaction waits before its batch is posted (in seconds) [default: 3]
+    #[arg(long, env = "SHARD_BATCH_INTERVAL")]
+    batch_interval: Option<u64>,
+
+    /// Number of pending transactions that triggers a batch right away [default: 1000]
+    #[arg(long, env = "SHARD_BATCH_MAX_TXS")]
+    batch_max_txs: Option<usize>,
+
+    /// Pending transaction bytes that trigger a batch right away [default: 1500000]
+    #[arg(long, env = "SHARD_BATCH_MAX_BYTES")]
+    batch_max_bytes: Option<usize>,
+
+    /// Maximum number of batch submissions awaiting inclusion at once [default: 4]
+    #[arg(long, env = "SHARD_MAX_INFLIGHT_SUBMISSIONS")]
+    max_inflight_submissions: Option<usize>,
+
+    /// How long to wait for the final batch on shutdown (in seconds) [default: 30]
+    #[arg(long, env = "SHARD_SHUTDOWN_TIMEOUT")]
+    shutdown_timeout: Option<u64>,
+
+    /// File to persist unposted transactions to on shutdown
+    #[arg(long, env = "SHARD_MEMPOOL_FILE")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mempool_file: Option<PathBuf>,
+
+    /// File to append rejected blobs and transactions to
+    #[arg(long, env = "SHARD_DEAD_LETTER_FILE")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dead_letter_file: Option<PathBuf>,
+
+    /// The address to listen on for the admin API (disabled if unset)
+    #[arg(long, env = "SHARD_ADMIN_LISTEN_ADDR")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    admin_listen_addr: Option<String>,
+
+    /// The bearer token required by the admin API
+    #[arg(long, env = "SHARD_ADMIN_TOKEN", hide_env_values = true)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    admin_token: Option<String>,
+
+    /// Directory to write state snapshots to
+    #[arg(long, env = "SHARD_SNAPSHOT_DIR")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snapshot_dir: Option<PathBuf>,
+}
+
+impl CommonArgs {
+    /// Fills in values that were not set on the command line or in the
+    /// environment from the config file, if one was given.
+    fn with_config_file(self) -> Result<Self> {
+        let Some(path) = &self.config else {
+            return Ok(self);
+        };
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        let file: CommonArgs = toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))?;
+        Ok(self.or(file))
+    }
+
+    /// Returns `self`, with unset values taken from `other`.
+    fn or(self, other: CommonArgs) -> CommonArgs {
+        CommonArgs {
+            config: self.config.or(other.config),
+            namespace: self.namespace.or(other.namespace),
+            role: self.role.or(other.role),
+            upstreams: self.upstreams.or(other.upstreams),
+            forward_retries: self.forward_retries.or(other.forward_retries),
+            peers: self.peers.or(other.peers),
+            posters: self.posters.or(other.posters),
+            poster_id: self.poster_id.or(other.poster_id),
+            slot_length: self.slot_length.or(other.slot_length),
+            failover_blocks: self.failover_blocks.or(other.failover_blocks),
+            sequencer_key: self.sequencer_key.or(other.sequencer_key),
+            sequencer_key_name: self.sequencer_key_name.or(other.sequencer_key_name),
+            forced_inclusion_delay: self.forced_inclusion_delay.or(other.forced_inclusion_delay),
+            start_height: self.start_height.or(other.start_height),
+            celestia_urls: self.celestia_urls.or(other.celestia_urls),
+            listen_addr: self.listen_addr.or(other.listen_addr),
+            auth_token: self.auth_token.or(other.auth_token),
+            cross_check_reads: self.cross_check_reads.or(other.cross_check_reads),
+            verify_proofs: self.verify_proofs.or(other.verify_proofs),
+            trusted_header: self.trusted_header.or(other.trusted_header),
+            gas_price: self.gas_price.or(other.gas_price),
+            gas_limit: self.gas_limit.or(other.gas_limit),
+            gas_estimation: self.gas_estimation.or(other.gas_estimation),
+            fee_granter: self.fee_granter.or(other.fee_granter),
+            celestia_key_name: self.celestia_key_name.or(other.celestia_key_name),
+            signed_blobs: self.signed_blobs.or(other.signed_blobs),
+            compression: self.compression.or(other.compression),
+            max_blob_size: self.max_blob_size.or(other.max_blob_size),
+            max_txs_per_blob: self.max_txs_per_blob.or(other.max_txs_per_blob),
+            batch_interval: self.batch_interval.or(other.batch_interval),
+            batch_max_txs: self.batch_max_txs.or(other.batch_max_txs),
+            batch_max_bytes: self.batch_max_bytes.or(other.batch_max_bytes),
+            max_inflight_submissions: self
+                .max_inflight_submissions
+                .or(other.max_inflight_submissions),
+            shutdown_timeout: self.shutdown_timeout.or(other.shutdown_timeout),
+            mempool_file: self.mempool_file.or(other.mempool_file),
+            dead_letter_file: self.dead_letter_file.or(other.dead_letter_file),
+            admin_listen_addr: self.admin_listen_addr.or(other.admin_listen_addr),
+            admin_token: self.admin_token.or(other.admin_token),
+            snapshot_dir: self.snapshot_dir.or(other.snapshot_dir),
+        }
+    }
+}
+
+impl From<&Config> for CommonArgs {
+    fn from(config: &Config) -> Self {
+        CommonArgs {
+            config: None,
+            namespace: Some(namespace_to_hex(&config.namespace)),
+            role: Some(config.role),
+            upstreams: Some(config.upstreams.clone()).filter(|upstreams| !upstreams.is_empty()),
+            forward_retries: Some(config.forward_retries),
+            peers: Some(config.peers.clone()).filter(|peers| !peers.is_empty()),
+            posters: Some(config.posters.clone()).filter(|posters| !posters.is_empty()),
+            poster_id: config.poster_id.clone(),
+            slot_length: Some(config.slot_length),
+            failover_blocks: Some(config.failover_blocks),
+            sequencer_key: config.sequencer_key.as_ref().map(|vk| vk.to_string()),
+            sequencer_key_name: config.sequencer_key_name.clone(),
+            forced_inclusion_delay: Some(config.forced_inclusion_delay),
+            start_height: Some(config.start_height),
+            celestia_urls: Some(config.celestia_urls.clone()),
+            listen_addr: Some(config.listen_addr.clone()),
+            auth_token: config.auth_token.clone(),
+            cross_check_reads: Some(config.cross_check_reads),
+            verify_proofs: Some(config.verify_proofs),
+            trusted_header: config
+                .trusted_header
+                .as_ref()
+                .map(|header| header.to_string()),
+            gas_price: config.gas_price,
+            gas_limit: config.gas_limit,
+            gas_estimation: Some(config.gas_estimation),
+            fee_granter: config.fee_granter.clone(),
+            celestia_key_name: config.celestia_key_name.clone(),
+            signed_blobs: Some(config.signed_blobs),
+            compression: Some(config.compression),
+            max_blob_size: Some(config.max_blob_size),
+            max_txs_per_blob: Some(config.max_txs_per_blob),
+            batch_interval: Some(config.batch_interval.as_secs()),
+            batch_max_txs: Some(config.batch_max_txs),
+            batch_max_bytes: Some(config.batch_max_bytes),
+            max_inflight_submissions: Some(config.max_inflight_submissions),
+            shutdown_timeout: Some(config.shutdown_timeout.as_secs()),
+            mempool_file: config.mempool_file.clone(),
+            dead_letter_file: config.dead_letter_file.clone(),
+            admin_listen_addr: config.admin_listen_addr.clone(),
+            admin_token: config.admin_token.clone(),
+            snapshot_dir: config.snapshot_dir.clone(),
+        }
+    }
 }
 
 #[derive(Subcommand, Debug)]
@@ -51,6 +343,10 @@ enum Command {
     SubmitTx(SubmitTxArgs),
     /// Create a signer
     CreateSigner(CreateSignerArgs),
+    /// Print the effective configuration, with secrets redacted
+    PrintConfig(CommonArgs),
+    /// List blobs and transactions the node rejected during sync
+    DeadLetters(DeadLettersArgs),
 }
 
 #[derive(Parser, Debug)]
@@ -64,6 +360,26 @@ struct SubmitTxArgs {
     #[arg(long, default_value = "0")]
     nonce: u64,
 
+    /// Post the transaction as a blob directly to Celestia instead of
+    /// submitting it to the node, using the configured Celestia URL and auth
+    /// token
+    #[arg(long)]
+    direct: bool,
+
+    #[command(flatten)]
+    common: CommonArgs,
+}
+
+#[derive(Parser, Debug)]
+struct DeadLettersArgs {
+    /// Only list dead letters from this Celestia height on
+    #[arg(long, default_value = "0")]
+    from_height: u64,
+
+    /// Maximum number of dead letters to list
+    #[arg(long, default_value = "100")]
+    limit: usize,
+
     #[command(flatten)]
     common: CommonArgs,
 }
@@ -79,13 +395,26 @@ struct CreateSignerArgs {
 struct Args {
     #[command(subcommand)]
     command: Command,
+
+    /// The log output format
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        env = "SHARD_LOG_FORMAT",
+        default_value = "pretty"
+    )]
+    log_format: LogFormat,
+
+    /// Log filter directives, e.g. `info,my_rollup::node=debug` (defaults to RUST_LOG)
+    #[arg(long, global = true, env = "SHARD_LOG_FILTER")]
+    log_filter: Option<String>,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    pretty_env_logger::init();
-
     let args = Args::parse();
+    init_logging(args.log_format, args.log_filter.as_deref())?;
 
     match args.command {
         Command::Serve(common_args) => {
@@ -96,39 +425,163 @@ async fn main() -> Result<()> {
             common,
             key_name,
             nonce,
+            direct,
             tx,
         }) => {
             let config = config_from_args(common)?;
-            submit_tx(config, key_name, nonce, tx).await
+            submit_tx(config, key_name, nonce, tx, direct).await
         }
         Command::CreateSigner(CreateSignerArgs { key_name }) => create_signer(key_name),
+        Command::PrintConfig(common_args) => {
+            let config = config_from_args(common_args)?;
+            print_config(&config)
+        }
+        Command::DeadLetters(DeadLettersArgs {
+            from_height,
+            limit,
+            common,
+        }) => {
+            let config = config_from_args(common)?;
+            list_dead_letters(&config, from_height, limit).await
+        }
     }
 }
 
+fn init_logging(format: LogFormat, filter: Option<&str>) -> Result<()> {
+    let filter = match filter {
+        Some(directives) => EnvFilter::try_new(directives).context("Invalid log filter")?,
+        None => {
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_FILTER))
+        }
+    };
+
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+    match format {
+        LogFormat::Pretty => subscriber.init(),
+        LogFormat::Json => subscriber.json().init(),
+    }
+    Ok(())
+}
+
 fn create_signer(key_name: String) -> Result<()> {
     let signer = keystore_rs::create_signing_key();
     keystore_rs::KeyChain
         .add_signing_key(key_name.as_str(), &signer)
         .map_err(|e| anyhow::anyhow!("Failed to create signer: {}", e))?;
-    info!("Signer '{}' created successfully", key_name);
+    let vk: VerifyingKey = signer.into();
+    info!(
+        "Signer '{}' created successfully with address {}",
+        key_name,
+        Address::from(&vk)
+    );
     Ok(())
 }
 
 fn config_from_args(args: CommonArgs) -> Result<Config> {
-    let namespace =
-        Namespace::new_v0(&hex::decode(&args.namespace).context("Invalid namespace hex")?)
-            .context("Failed to create namespace")?;
+    let args = args.with_config_file()?;
+    let defaults = Config::default();
+
+    let namespace = args.namespace.as_deref().unwrap_or(DEFAULT_NAMESPACE);
+    let namespace = Namespace::new_v0(&hex::decode(namespace).context("Invalid namespace hex")?)
+        .context("Failed to create namespace")?;
 
     Ok(Config {
         namespace,
-        start_height: args.start_height,
-        celestia_url: args.celestia_url,
-        listen_addr: args.listen_addr,
-        auth_token: args.auth_token,
-        batch_interval: Duration::from_secs(args.batch_interval),
+        role: args.role.unwrap_or(defaults.role),
+        upstreams: args.upstreams.unwrap_or(defaults.upstreams),
+        forward_retries: args.forward_retries.unwrap_or(defaults.forward_retries),
+        peers: args.peers.unwrap_or(defaults.peers),
+        posters: args.posters.unwrap_or(defaults.posters),
+        poster_id: args.poster_id.or(defaults.poster_id),
+        slot_length: args.slot_length.unwrap_or(defaults.slot_length),
+        failover_blocks: args.failover_blocks.unwrap_or(defaults.failover_blocks),
+        sequencer_key: args
+            .sequencer_key
+            .as_deref()
+            .map(address::parse_verifying_key_arg)
+            .transpose()
+            .context("Invalid sequencer key")?
+            .or(defaults.sequencer_key),
+        sequencer_key_name: args.sequencer_key_name.or(defaults.sequencer_key_name),
+        forced_inclusion_delay: args
+            .forced_inclusion_delay
+            .unwrap_or(defaults.forced_inclusion_delay),
+        start_height: args.start_height.unwrap_or(defaults.start_height),
+        celestia_urls: args.celestia_urls.unwrap_or(defaults.celestia_urls),
+        listen_addr: args.listen_addr.unwrap_or(defaults.listen_addr),
+        auth_token: args.auth_token.or(defaults.auth_token),
+        cross_check_reads: args.cross_check_reads.unwrap_or(defaults.cross_check_reads),
+        verify_proofs: args.verify_proofs.unwrap_or(defaults.verify_proofs),
+        trusted_header: args
+            .trusted_header
+            .as_deref()
+            .map(str::parse)
+            .transpose()
+            .context("Invalid trusted header")?
+            .or(defaults.trusted_header),
+        gas_price: args.gas_price.or(defaults.gas_price),
+        gas_limit: args.gas_limit.or(defaults.gas_limit),
+        gas_estimation: args.gas_estimation.unwrap_or(defaults.gas_estimation),
+        fee_granter: args.fee_granter.or(defaults.fee_granter),
+        celestia_key_name: args.celestia_key_name.or(defaults.celestia_key_name),
+        signed_blobs: args.signed_blobs.unwrap_or(defaults.signed_blobs),
+        compression: args.compression.unwrap_or(defaults.compression),
+        max_blob_size: args.max_blob_size.unwrap_or(defaults.max_blob_size),
+        max_txs_per_blob: args.max_txs_per_blob.unwrap_or(defaults.max_txs_per_blob),
+        batch_interval: args
+            .batch_interval
+            .map(Duration::from_secs)
+            .unwrap_or(defaults.batch_interval),
+        batch_max_txs: args.batch_max_txs.unwrap_or(defaults.batch_max_txs),
+        batch_max_bytes: args.batch_max_bytes.unwrap_or(defaults.batch_max_bytes),
+        max_inflight_submissions: args
+            .max_inflight_submissions
+            .unwrap_or(defaults.max_inflight_submissions),
+        shutdown_timeout: args
+            .shutdown_timeout
+            .map(Duration::from_secs)
+            .unwrap_or(defaults.shutdown_timeout),
+        mempool_file: args.mempool_file.or(defaults.mempool_file),
+        dead_letter_file: args.dead_letter_file.or(defaults.dead_letter_file),
+        admin_listen_addr: args.admin_listen_addr.or(defaults.admin_listen_addr),
+        admin_token: args.admin_token.or(defaults.admin_token),
+        snapshot_dir: args.snapshot_dir.or(defaults.snapshot_dir),
     })
 }
 
+fn print_config(config: &Config) -> Result<()> {
+    let mut printable = CommonArgs::from(config);
+    if printable.auth_token.is_some() {
+        printable.auth_token = Some(REDACTED.to_string());
+    }
+    if printable.admin_token.is_some() {
+        printable.admin_token = Some(REDACTED.to_string());
+    }
+    print!("{}", toml::to_string(&printable)?);
+    Ok(())
+}
+
+/// Fetches the dead letters of the node and prints them as JSON lines.
+async fn list_dead_letters(config: &Config, from_height: u64, limit: usize) -> Result<()> {
+    let url = format!("http://{}/dead_letters", config.listen_addr);
+    let response = reqwest::Client::new()
+        .get(url)
+        .query(&[("from_height", from_height), ("limit", limit as u64)])
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!(
+            "Failed to list dead letters: {}",
+            response.text().await?
+        ));
+    }
+
+    for letter in response.json::<Vec<DeadLetter>>().await? {
+        println!("{}", serde_json::to_string(&letter)?);
+    }
+    Ok(())
+}
+
 async fn start_node(config: Config) -> Result<()> {
     let node = Arc::new(Node::new(config).await?);
 
@@ -142,9 +595,8 @@ async fn submit_tx(
     key_name: String,
     nonce: u64,
     tx_variant: TransactionType,
+    direct: bool,
 ) -> Result<()> {
-    let url = format!("http://{}/submit_tx", config.listen_addr);
-
     let tx = if SIGNATURE_VERIFICATION_ENABLED {
         let signer = keystore_rs::KeyChain
             .get_signing_key(key_name.as_str())
@@ -155,6 +607,7 @@ async fn submit_tx(
             nonce,
             vk,
             tx_type: tx_variant,
+            blob_signer: None,
         };
 
         // TODO: ugly api
@@ -166,14 +619,24 @@ async fn submit_tx(
             nonce: 0,
             vk: VerifyingKey::Ed25519(keystore_rs::create_signing_key().verification_key()),
             tx_type: tx_variant,
+            blob_signer: None,
         }
     };
 
+    if direct {
+        return submit_tx_direct(&config, &tx).await;
+    }
+
+    let url = format!("http://{}/submit_tx", config.listen_addr);
     let client = reqwest::Client::new();
     let response = client.post(url).json(&tx).send().await?;
 
     if response.status().is_success() {
-        info!("Transaction submitted successfully");
+        info!(
+            tx_hash = %tx.hash(),
+            "Transaction submitted successfully from {}",
+            tx.sender()
+        );
         Ok(())
     } else {
         Err(anyhow::anyhow!(
@@ -182,3 +645,44 @@ async fn submit_tx(
         ))
     }
 }
+
+/// Posts a transaction as its own blob in the rollup namespace, so it is
+/// included even if no node accepts it.
+async fn submit_tx_direct(config: &Config, tx: &Transaction) -> Result<()> {
+    let client = DaClient::connect(&config.celestia_urls, config.auth_token.clone(), false).await?;
+
+    let data = envelope::seal(
+        PayloadKind::Transaction,
+        &bincode::serialize(tx)?,
+        config.compression,
+    )?;
+    let tx_config = fees::tx_config(config, data.len());
+    let blob = if config.signed_blobs {
+        let address = client
+            .request(|client| async move { StateClient::state_account_address(&*client).await })
+            .await
+            .context("Failed to get your Celestia account")?;
+        let CelestiaAddress::AccAddress(signer) = address else {
+            anyhow::bail!("the Celestia node does not use an account address");
+        };
+        Blob::new_with_signer(config.namespace, data, signer, APP_VERSION)?
+    } else {
+        Blob::new(config.namespace, data, APP_VERSION)?
+    };
+    let height = client
+        .request(|client| {
+            let blobs = [blob.clone()];
+            let tx_config = tx_config.clone();
+            async move { BlobClient::blob_submit(&*client, &blobs, tx_config).await }
+        })
+        .await
+        .context("Failed to submit transaction blob to Celestia")?;
+
+    info!(
+        tx_hash = %tx.hash(),
+        "Transaction from {} included at Celestia height {}",
+        tx.sender(),
+        height
+    );
+    Ok(())
+}
@@ -1,8 +1,22 @@
 pub const CARGO_TEMPLATE: &str = include_str!("./Cargo.toml");
 pub const CARGO_LOCK_TEMPLATE: &str = include_str!("./Cargo.lock");
+pub const ADMIN_RS: &str = include_str!("./admin.rs");
+pub const ADDRESS_RS: &str = include_str!("./address.rs");
+pub const DA_RS: &str = include_str!("./da.rs");
+pub const DEADLETTER_RS: &str = include_str!("./deadletter.rs");
+pub const ENVELOPE_RS: &str = include_str!("./envelope.rs");
+pub const FEES_RS: &str = include_str!("./fees.rs");
+pub const FORKS_RS: &str = include_str!("./forks.rs");
+pub const FORWARDER_RS: &str = include_str!("./forwarder.rs");
+pub const GOSSIP_RS: &str = include_str!("./gossip.rs");
 pub const LIB_RS: &str = include_str!("./lib.rs");
 pub const MAIN_RS: &str = include_str!("./main.rs");
+pub const METRICS_RS: &str = include_str!("./metrics.rs");
 pub const NODE_RS: &str = include_str!("./node.rs");
+pub const PROOFS_RS: &str = include_str!("./proofs.rs");
+pub const SCHEDULE_RS: &str = include_str!("./schedule.rs");
+pub const SEQUENCER_RS: &str = include_str!("./sequencer.rs");
 pub const STATE_RS: &str = include_str!("./state.rs");
+pub const TESTING_RS: &str = include_str!("./testing.rs");
 pub const TX_RS: &str = include_str!("./tx.rs");
 pub const SERVER_RS: &str = include_str!("./webserver.rs");
@@ -1,24 +1,138 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use async_lock::Mutex;
-use axum::routing::post;
+use axum::routing::{get, post};
 use axum::Router;
-use celestia_rpc::{BlobClient, HeaderClient};
-use celestia_types::{nmt::Namespace, Blob, TxConfig};
+use celestia_rpc::{HeaderClient, ShareClient, StateClient};
+use celestia_types::state::{AccAddress, Address as CelestiaAddress};
+use celestia_types::{nmt::Namespace, AppVersion, Blob, ExtendedHeader};
+use clap::ValueEnum;
+use keystore_rs::KeyStore;
+use prism_common::keys::{SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::Notify;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, Notify, Semaphore};
+use tokio::task::{JoinHandle, JoinSet};
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+use tokio_util::sync::CancellationToken;
+use tower::ServiceBuilder;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::trace::TraceLayer;
+use tracing::instrument;
 
+use crate::admin;
+use crate::da::DaClient;
+use crate::deadletter::{BlobRef, DeadLetter, DeadLetterStore};
+use crate::envelope::{self, Compression, PayloadKind};
+use crate::fees::{self, GasEstimation};
+use crate::forks;
+use crate::forwarder::Forwarder;
+use crate::gossip::Gossip;
+use crate::metrics::Metrics;
+use crate::proofs::{self, TrustedHeader};
+use crate::schedule::PostingSchedule;
+use crate::sequencer::{BatchHash, InboxBatch, InboxOutput, SequencerInbox, SignedBatch};
 use crate::tx::Batch;
-use crate::webserver::submit_tx;
-use crate::{state::State, tx::Transaction};
+use crate::webserver::{
+    dead_letters, forward_tx, gossip_tx, health, make_request_span, metrics, ready, status,
+    submit_tx,
+};
+use crate::{address::Address, state::State, tx::Transaction};
 
 const DEFAULT_BATCH_INTERVAL: Duration = Duration::from_secs(3);
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_FORWARD_RETRIES: u32 = 3;
+const DEFAULT_SLOT_LENGTH: u64 = 10;
+const DEFAULT_FAILOVER_BLOCKS: u64 = 5;
+const DEFAULT_FORCED_INCLUSION_DELAY: u64 = 50;
+/// Comfortably below the ~1.9 MB that fit into Celestia's default 64x64
+/// square.
+const DEFAULT_MAX_BLOB_SIZE: usize = 1_500_000;
+const DEFAULT_MAX_TXS_PER_BLOB: usize = 1_000;
+const DEFAULT_MAX_INFLIGHT_SUBMISSIONS: usize = 4;
+
+/// How long to wait before resubmitting a signed batch that failed.
+const SIGNED_BATCH_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Bytes reserved in each blob besides the transactions (the envelope header,
+/// the length prefix, and batch number, previous hash and signature of signed
+/// batches).
+const BLOB_OVERHEAD: usize = 256;
+
+/// Celestia app version that blobs are created for. Share-version-1 blobs
+/// require at least v3.
+pub const APP_VERSION: AppVersion = AppVersion::V3;
+
+/// How often the Celestia nodes are checked for health.
+const DA_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How long to wait before subscribing again after no Celestia node accepted
+/// the subscription.
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(2);
+
+/// Number of processed-block notifications buffered for slow subscribers.
+const BLOCK_NOTIFICATION_CAPACITY: usize = 128;
+
+/// What a node does besides syncing the rollup state.
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeRole {
+    /// Accepts transactions on `/submit_tx` and posts them to Celestia.
+    #[default]
+    Sequencer,
+    /// Only syncs and serves queries, without needing Celestia funds.
+    /// Transactions are forwarded to [`Config::upstreams`] or gossiped to
+    /// [`Config::peers`], if configured.
+    Follower,
+}
 
 #[derive(Clone)]
 pub struct Config {
     /// The namespace used by this rollup.
     pub namespace: Namespace,
 
+    /// Whether the node accepts and posts transactions.
+    pub role: NodeRole,
+
+    /// Base URLs of the sequencer nodes that follower nodes forward submitted
+    /// transactions to, in order of preference.
+    pub upstreams: Vec<String>,
+    /// How often to retry all upstreams before giving up on a transaction.
+    pub forward_retries: u32,
+
+    /// Base URLs of the nodes to gossip transactions with. Gossip is disabled
+    /// if empty.
+    pub peers: Vec<String>,
+
+    /// Ids of the sequencer nodes that take turns posting batches, see
+    /// [`PostingSchedule`]. Every sequencer posts on its own if empty.
+    pub posters: Vec<String>,
+    /// The id of this node in [`Config::posters`].
+    pub poster_id: Option<String>,
+    /// Number of DA blocks in each poster's slot.
+    pub slot_length: u64,
+    /// Number of DA blocks a pending transaction waits before the next
+    /// poster takes over a lagging poster's slot.
+    pub failover_blocks: u64,
+
+    /// Key of the centralized sequencer. If set, only batches signed with it
+    /// are executed in order, and any other blob is only executed
+    /// [`Config::forced_inclusion_delay`] DA blocks after its inclusion, see
+    /// [`SequencerInbox`].
+    pub sequencer_key: Option<VerifyingKey>,
+    /// Name of the keystore key this node signs batches with. Must belong to
+    /// [`Config::sequencer_key`].
+    pub sequencer_key_name: Option<String>,
+    /// Number of DA blocks after which unsigned blobs are executed in
+    /// sequencer mode.
+    pub forced_inclusion_delay: u64,
+
     /// The height from which to start syncing.
     // TODO: Backwards sync, accepting trusted state (celestia blocks get
     // pruned)
@@ -27,104 +141,1160 @@ pub struct Config {
     /// The address to listen on for the node's webserver.
     pub listen_addr: String,
 
-    /// The URL of the Celestia node to connect to.
+    /// The URLs of the Celestia nodes to connect to. Requests go to one node
+    /// at a time and fail over to the others, see [`DaClient`].
     // TODO: Move fully to Lumina, only use a url for posting transactions
     // until p2p tx transmission is implemented
-    pub celestia_url: String,
+    pub celestia_urls: Vec<String>,
     /// The auth token to use when connecting to Celestia.
     pub auth_token: Option<String>,
+    /// Compare the blobs of each height with a second Celestia node, and stop
+    /// syncing if they differ.
+    pub cross_check_reads: bool,
+    /// Verify the blobs of each height against its extended header, with
+    /// namespace inclusion and absence proofs, and stop syncing if they do
+    /// not match.
+    pub verify_proofs: bool,
+    /// Header that headers are verified against with
+    /// [`Config::verify_proofs`]. Must not be above [`Config::start_height`].
+    pub trusted_header: Option<TrustedHeader>,
+
+    /// Gas price for blob submissions in utia. The Celestia node estimates
+    /// it if unset.
+    pub gas_price: Option<f64>,
+    /// Gas limit for blob submissions, overriding [`Config::gas_estimation`].
+    pub gas_limit: Option<u64>,
+    /// How the gas limit is chosen if [`Config::gas_limit`] is unset.
+    pub gas_estimation: GasEstimation,
+    /// Account that pays the fees of blob submissions through a fee grant.
+    pub fee_granter: Option<String>,
+    /// Name of the key in the Celestia node's keyring to submit blobs with.
+    /// The node's default key is used if unset.
+    pub celestia_key_name: Option<String>,
 
-    /// The interval at which to post batches of transactions.
+    /// Post batches and transactions submitted with `submit-tx --direct` as
+    /// share-version-1 blobs, which carry the submitting Celestia account as
+    /// their signer. Only single transactions get a
+    /// [`Transaction::blob_signer`], never the transactions of a batch.
+    pub signed_blobs: bool,
+
+    /// How batches are compressed before posting.
+    pub compression: Compression,
+
+    /// Maximum size of a single blob in bytes. Larger batches are split into
+    /// several blobs.
+    pub max_blob_size: usize,
+    /// Maximum number of transactions in a single blob.
+    pub max_txs_per_blob: usize,
+
+    /// The longest a transaction waits before its batch is posted.
     pub batch_interval: Duration,
+    /// Number of pending transactions at which a batch is posted right away.
+    pub batch_max_txs: usize,
+    /// Encoded size of the pending transactions at which a batch is posted
+    /// right away.
+    pub batch_max_bytes: usize,
+    /// Maximum number of batch submissions awaiting inclusion at the same
+    /// time. The blobs of a split batch are submitted one after another as
+    /// one submission. Once all are in flight and the mempool holds
+    /// [`Config::batch_max_txs`] transactions, new transactions are rejected.
+    pub max_inflight_submissions: usize,
+
+    /// How long to wait for the final batch and the webserver on shutdown.
+    pub shutdown_timeout: Duration,
+
+    /// File to persist unposted transactions to on shutdown. They are queued
+    /// again on the next start.
+    pub mempool_file: Option<PathBuf>,
+
+    /// File that rejected blobs and transactions are appended to, so they are
+    /// kept across restarts. They are only kept in memory if unset.
+    pub dead_letter_file: Option<PathBuf>,
+
+    /// The address to listen on for the admin API. The admin API is disabled
+    /// if unset.
+    pub admin_listen_addr: Option<String>,
+    /// The bearer token required by the admin API.
+    pub admin_token: Option<String>,
+
+    /// Directory that state snapshots are written to, when triggered through
+    /// the admin API or on shutdown.
+    pub snapshot_dir: Option<PathBuf>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Config {
             namespace: Namespace::new_v0(&[42, 42, 42, 42]).unwrap(),
+            role: NodeRole::default(),
+            upstreams: Vec::new(),
+            forward_retries: DEFAULT_FORWARD_RETRIES,
+            peers: Vec::new(),
+            posters: Vec::new(),
+            poster_id: None,
+            slot_length: DEFAULT_SLOT_LENGTH,
+            failover_blocks: DEFAULT_FAILOVER_BLOCKS,
+            sequencer_key: None,
+            sequencer_key_name: None,
+            forced_inclusion_delay: DEFAULT_FORCED_INCLUSION_DELAY,
             start_height: 1,
             listen_addr: "0.0.0.0:3000".to_string(),
-            celestia_url: "ws://0.0.0.0:26658".to_string(),
+            celestia_urls: vec!["ws://0.0.0.0:26658".to_string()],
             auth_token: None,
+            cross_check_reads: false,
+            verify_proofs: false,
+            trusted_header: None,
+            gas_price: None,
+            gas_limit: None,
+            gas_estimation: GasEstimation::default(),
+            fee_granter: None,
+            celestia_key_name: None,
+            signed_blobs: false,
+            compression: Compression::default(),
+            max_blob_size: DEFAULT_MAX_BLOB_SIZE,
+            max_txs_per_blob: DEFAULT_MAX_TXS_PER_BLOB,
             batch_interval: DEFAULT_BATCH_INTERVAL,
+            batch_max_txs: DEFAULT_MAX_TXS_PER_BLOB,
+            batch_max_bytes: DEFAULT_MAX_BLOB_SIZE,
+            max_inflight_submissions: DEFAULT_MAX_INFLIGHT_SUBMISSIONS,
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            mempool_file: None,
+            dead_letter_file: None,
+            admin_listen_addr: None,
+            admin_token: None,
+            snapshot_dir: None,
+        }
+    }
+}
+
+/// Snapshot of the node's sync progress, served on `/status`.
+#[derive(Serialize, Debug, Clone)]
+pub struct NodeStatus {
+    /// The rollup namespace (hex encoded).
+    pub namespace: String,
+    pub start_height: u64,
+    /// The last Celestia height processed by the node.
+    pub last_processed_height: u64,
+    /// The latest Celestia height known to the node.
+    pub network_head: u64,
+    /// Number of transactions waiting to be posted.
+    pub mempool_size: usize,
+    /// Whether historical sync has completed.
+    pub synced: bool,
+    /// Whether batch posting was paused through the admin API.
+    pub batching_paused: bool,
+    pub role: NodeRole,
+    /// Protocol version of the last processed height, see [`forks`].
+    pub protocol_version: forks::ProtocolVersion,
+}
+
+/// Why [`Node::queue_transaction`] rejected a transaction.
+#[derive(Debug)]
+pub enum QueueError {
+    /// The node does not accept transactions right now, e.g. because it is
+    /// shutting down
+    Unavailable(&'static str),
+    /// All submission slots are busy and the mempool holds a full batch
+    Congested,
+    /// The transaction was submitted before
+    Duplicate,
+    /// The transaction is invalid against the current state
+    Invalid(anyhow::Error),
+}
+
+impl fmt::Display for QueueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueueError::Unavailable(reason) => f.write_str(reason),
+            QueueError::Congested => {
+                f.write_str("too many blob submissions in flight, try again later")
+            }
+            QueueError::Duplicate => f.write_str("transaction already submitted"),
+            QueueError::Invalid(e) => write!(f, "{}", e),
         }
     }
 }
 
+impl std::error::Error for QueueError {}
+
+/// Sent to subscribers of [`NodeHandle::processed_blocks`] after each
+/// Celestia block has been applied to the state.
+#[derive(Serialize, Debug, Clone)]
+pub struct ProcessedBlock {
+    pub da_height: u64,
+    /// Number of transactions applied to the state.
+    pub tx_count: usize,
+    /// Number of transactions that failed to process.
+    pub failed_tx_count: usize,
+}
+
+/// A queued transaction, as listed by the admin API.
+#[derive(Serialize, Debug, Clone)]
+pub struct MempoolEntry {
+    pub tx_hash: String,
+    pub sender: Address,
+    pub nonce: u64,
+}
+
+/// State written by [`Node::snapshot_state`], together with the last Celestia
+/// height included in it.
+#[derive(Serialize)]
+struct StateSnapshot<'a> {
+    da_height: u64,
+    state: &'a State,
+}
+
+/// A transaction in the mempool.
+struct PendingTx {
+    tx: Transaction,
+    /// DA height at which the transaction was queued
+    queued_at: u64,
+}
+
+/// A blob of a batch, waiting to be submitted.
+struct PendingBlob {
+    blob: Blob,
+    txs: Vec<Transaction>,
+    /// Number of the signed batch in the blob, in sequencer mode
+    batch_number: Option<u64>,
+}
+
 pub struct Node {
-    da_client: celestia_rpc::Client,
+    da_client: DaClient,
     cfg: Config,
 
     /// The state of the rollup that is mutated by incoming transactions
     state: Arc<Mutex<State>>,
 
     /// Transactions that have been queued for batch posting to Celestia
-    pending_transactions: Arc<Mutex<Vec<Transaction>>>,
+    pending_transactions: Arc<Mutex<Vec<PendingTx>>>,
+
+    /// Prometheus metrics served on `/metrics`
+    pub(crate) metrics: Metrics,
 
     /// Used to notify the syncer that genesis sync has completed, and queued
     /// stored blocks from incoming sync can be processed
     genesis_sync_completed: Notify,
+
+    /// Set together with [`Node::genesis_sync_completed`], so readiness can be
+    /// queried at any time
+    genesis_synced: AtomicBool,
+
+    /// Cancelled when the node shuts down
+    shutdown: CancellationToken,
+
+    /// Batch posting is skipped while set, see the admin API
+    batching_paused: AtomicBool,
+
+    /// The current batch interval in milliseconds, initialized from
+    /// [`Config::batch_interval`] and changeable at runtime
+    batch_interval_ms: AtomicU64,
+
+    /// Encoded size of the pending transactions
+    pending_bytes: AtomicUsize,
+
+    /// Wakes up batch posting when the mempool receives its first transaction
+    batch_started: Notify,
+
+    /// Wakes up batch posting when the mempool reaches
+    /// [`Config::batch_max_txs`] or [`Config::batch_max_bytes`]
+    batch_full: Notify,
+
+    /// Limits the blob submissions in flight, see
+    /// [`Config::max_inflight_submissions`]
+    submission_slots: Arc<Semaphore>,
+
+    /// Notifies subscribers about each processed Celestia block
+    processed_blocks: broadcast::Sender<ProcessedBlock>,
+
+    /// Forwards submitted transactions to upstream sequencers, only set on
+    /// follower nodes with configured upstreams
+    pub(crate) forwarder: Option<Forwarder>,
+
+    /// Propagates transactions to peers, only set if peers are configured
+    gossip: Option<Arc<Gossip>>,
+
+    /// Decides when this node may post, only set if posters are configured
+    schedule: Option<PostingSchedule>,
+
+    /// DA height at which the oldest pending transaction was queued, or 0 if
+    /// the mempool is empty
+    pending_since: AtomicU64,
+
+    /// The last header verified by [`Node::verify_blobs`], so the next one
+    /// can be checked against it
+    verified_header: Mutex<Option<ExtendedHeader>>,
+
+    /// Blobs and transactions that were rejected during sync
+    dead_letters: DeadLetterStore,
+
+    /// Orders signed batches and forced-inclusion blobs, only set in
+    /// sequencer mode
+    inbox: Option<Mutex<SequencerInbox>>,
+
+    /// Key to sign batches with, only set on the centralized sequencer
+    batch_signer: Option<SigningKey>,
+
+    /// Number and hash of the last batch this node signed, ahead of
+    /// [`Node::inbox`] while batches wait for inclusion
+    posted_head: Mutex<Option<(u64, BatchHash)>>,
+
+    /// Celestia account of this node, set if [`Config::signed_blobs`] is
+    /// enabled
+    blob_signer: Option<AccAddress>,
 }
 
-impl Node {
-    pub async fn new(cfg: Config) -> Result<Self> {
-        let auth_token: Option<&str> = cfg.auth_token.as_deref();
+/// Builds a [`Node`], for embedding it into other services or tests.
+#[derive(Default)]
+pub struct NodeBuilder {
+    cfg: Config,
+    state: Option<State>,
+}
 
-        let da_client = celestia_rpc::Client::new(&cfg.celestia_url, auth_token)
-            .await
-            .context("Couldn't start RPC connection to celestia-node instance")?;
+impl NodeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn config(mut self, cfg: Config) -> Self {
+        self.cfg = cfg;
+        self
+    }
+
+    /// Sets the state to start syncing from, instead of [`State::new`].
+    pub fn state(mut self, state: State) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    /// Connects to Celestia and constructs the node without starting it.
+    pub async fn build(self) -> Result<Node> {
+        let cfg = self.cfg;
+        let da_client = DaClient::connect(
+            &cfg.celestia_urls,
+            cfg.auth_token.clone(),
+            cfg.cross_check_reads,
+        )
+        .await?;
+
+        if cfg.max_blob_size <= BLOB_OVERHEAD {
+            bail!("max blob size must be larger than {} bytes", BLOB_OVERHEAD);
+        }
+        if cfg.max_txs_per_blob == 0 {
+            bail!("max transactions per blob must be at least 1");
+        }
+        if cfg.max_inflight_submissions == 0 {
+            bail!("max in-flight submissions must be at least 1");
+        }
+        if cfg.admin_listen_addr.is_some() && cfg.admin_token.is_none() {
+            bail!("the admin API requires an admin token");
+        }
+        if cfg.verify_proofs {
+            let Some(trusted) = &cfg.trusted_header else {
+                bail!("verifying proofs requires a trusted header");
+            };
+            if trusted.height > cfg.start_height {
+                bail!(
+                    "the trusted header at height {} is above the start height {}",
+                    trusted.height,
+                    cfg.start_height
+                );
+            }
+        }
+
+        let forwarder = match cfg.role {
+            NodeRole::Follower if !cfg.upstreams.is_empty() => {
+                Some(Forwarder::new(cfg.upstreams.clone(), cfg.forward_retries)?)
+            }
+            NodeRole::Sequencer if !cfg.upstreams.is_empty() => {
+                warn!("upstreams are only used by follower nodes, ignoring them");
+                None
+            }
+            _ => None,
+        };
+
+        let gossip = if cfg.peers.is_empty() {
+            None
+        } else {
+            Some(Arc::new(Gossip::new(cfg.peers.clone())?))
+        };
+
+        let schedule = match (&cfg.poster_id, cfg.role) {
+            (_, NodeRole::Follower) => None,
+            (_, NodeRole::Sequencer) if cfg.posters.is_empty() => None,
+            (Some(poster_id), NodeRole::Sequencer) => Some(PostingSchedule::new(
+                cfg.posters.clone(),
+                poster_id,
+                cfg.slot_length,
+                cfg.failover_blocks,
+            )?),
+            (None, NodeRole::Sequencer) => bail!("posters are configured, but no poster id"),
+        };
+
+        let batch_signer = match &cfg.sequencer_key_name {
+            Some(key_name) => {
+                let Some(sequencer_key) = &cfg.sequencer_key else {
+                    bail!("a sequencer key name is configured, but no sequencer key");
+                };
+                let signer = keystore_rs::KeyChain
+                    .get_signing_key(key_name)
+                    .map_err(|e| anyhow!("Failed to load sequencer key '{}': {}", key_name, e))?;
+                let vk: VerifyingKey = signer.clone().into();
+                if vk != *sequencer_key {
+                    bail!("key '{}' is not the configured sequencer key", key_name);
+                }
+                Some(SigningKey::Ed25519(Box::new(signer)))
+            }
+            None => None,
+        };
+        let inbox = cfg
+            .sequencer_key
+            .clone()
+            .map(|key| Mutex::new(SequencerInbox::new(key, cfg.forced_inclusion_delay)));
+
+        let blob_signer = if cfg.signed_blobs {
+            let address = da_client
+                .request(|client| async move { StateClient::state_account_address(&*client).await })
+                .await
+                .context("Failed to get the Celestia account of the node")?;
+            match address {
+                CelestiaAddress::AccAddress(address) => Some(address),
+                _ => bail!("the Celestia node does not use an account address"),
+            }
+        } else {
+            None
+        };
+
+        let dead_letters = DeadLetterStore::open(cfg.dead_letter_file.clone())?;
+
+        // persisted transactions were queued before the restart
+        let pending_transactions: Vec<PendingTx> = match (&cfg.mempool_file, cfg.role) {
+            (Some(path), NodeRole::Sequencer) => load_mempool(path)?
+                .into_iter()
+                .map(|tx| PendingTx { tx, queued_at: 0 })
+                .collect(),
+            _ => Vec::new(),
+        };
 
         Ok(Node {
-            cfg,
             da_client,
             genesis_sync_completed: Notify::new(),
-            pending_transactions: Arc::new(Mutex::new(Vec::new())),
-            state: Arc::new(Mutex::new(State::new())),
+            genesis_synced: AtomicBool::new(false),
+            shutdown: CancellationToken::new(),
+            batching_paused: AtomicBool::new(false),
+            batch_interval_ms: AtomicU64::new(cfg.batch_interval.as_millis() as u64),
+            pending_bytes: AtomicUsize::new(
+                pending_transactions
+                    .iter()
+                    .map(|pending| encoded_size(&pending.tx))
+                    .sum(),
+            ),
+            batch_started: Notify::new(),
+            batch_full: Notify::new(),
+            submission_slots: Arc::new(Semaphore::new(cfg.max_inflight_submissions)),
+            pending_transactions: Arc::new(Mutex::new(pending_transactions)),
+            state: Arc::new(Mutex::new(self.state.unwrap_or_default())),
+            metrics: Metrics::new().context("Failed to register metrics")?,
+            processed_blocks: broadcast::channel(BLOCK_NOTIFICATION_CAPACITY).0,
+            forwarder,
+            gossip,
+            schedule,
+            pending_since: AtomicU64::new(0),
+            verified_header: Mutex::new(None),
+            dead_letters,
+            inbox,
+            batch_signer,
+            posted_head: Mutex::new(None),
+            blob_signer,
+            cfg,
         })
     }
 
-    pub async fn queue_transaction(&self, tx: Transaction) -> Result<()> {
-        self.state.lock().await.validate_tx(tx.clone())?;
-        self.pending_transactions.lock().await.push(tx);
+    /// Builds the node and spawns its tasks.
+    pub async fn start(self) -> Result<NodeHandle> {
+        Ok(Arc::new(self.build().await?).spawn())
+    }
+}
+
+impl Node {
+    pub async fn new(cfg: Config) -> Result<Self> {
+        NodeBuilder::new().config(cfg).build().await
+    }
+
+    pub fn builder() -> NodeBuilder {
+        NodeBuilder::new()
+    }
+
+    #[instrument(skip_all, fields(tx_hash = %tx.hash()))]
+    pub async fn queue_transaction(&self, tx: Transaction) -> Result<(), QueueError> {
+        if self.cfg.role == NodeRole::Follower && self.gossip.is_none() {
+            return Err(QueueError::Unavailable(
+                "follower nodes do not accept transactions",
+            ));
+        }
+        if self.shutdown.is_cancelled() {
+            return Err(QueueError::Unavailable("node is shutting down"));
+        }
+        if self.is_congested().await {
+            return Err(QueueError::Congested);
+        }
+        self.validate_transaction(&tx)
+            .await
+            .map_err(QueueError::Invalid)?;
+
+        if let Some(gossip) = &self.gossip {
+            if !gossip.mark_seen(tx.hash()).await {
+                return Err(QueueError::Duplicate);
+            }
+            gossip.broadcast(tx.clone());
+        }
+
+        // followers only pass transactions on to their peers
+        if self.cfg.role == NodeRole::Sequencer {
+            self.push_pending(tx).await;
+        }
         Ok(())
     }
 
-    async fn post_pending_batch(&self) -> Result<Batch> {
+    /// Handles a transaction gossiped by a peer. Transactions seen before are
+    /// ignored, new ones are validated, queued on sequencer nodes and passed on
+    /// to this node's peers. Returns false if the transaction was a duplicate.
+    #[instrument(skip_all, fields(tx_hash = %tx.hash()))]
+    pub async fn receive_gossiped_transaction(&self, tx: Transaction) -> Result<bool> {
+        let gossip = self.gossip.as_ref().context("gossip is disabled")?;
+
+        let tx_hash = tx.hash();
+        if gossip.has_seen(&tx_hash).await {
+            return Ok(false);
+        }
+        self.validate_transaction(&tx).await?;
+        if !gossip.mark_seen(tx_hash).await {
+            return Ok(false);
+        }
+
+        gossip.broadcast(tx.clone());
+        if self.cfg.role == NodeRole::Sequencer && !self.shutdown.is_cancelled() {
+            self.push_pending(tx).await;
+        }
+        Ok(true)
+    }
+
+    async fn push_pending(&self, tx: Transaction) {
+        debug!("queued transaction from {}", tx.sender());
+        let queued_at = self.metrics.network_head.get() as u64;
         let mut pending_txs = self.pending_transactions.lock().await;
         if pending_txs.is_empty() {
-            return Ok(Batch::new(Vec::new()));
+            self.pending_since.store(queued_at, Ordering::Release);
+            self.batch_started.notify_one();
         }
+        let tx_size = encoded_size(&tx);
+        pending_txs.push(PendingTx { tx, queued_at });
+        self.metrics.mempool_size.set(pending_txs.len() as i64);
 
-        let batch = Batch::new(pending_txs.drain(..).collect());
-        let encoded_batch = bincode::serialize(&batch)?;
-        let blob = Blob::new(self.cfg.namespace, encoded_batch)?;
+        let pending_bytes = self.pending_bytes.fetch_add(tx_size, Ordering::AcqRel) + tx_size;
+        if pending_txs.len() >= self.cfg.batch_max_txs || pending_bytes >= self.cfg.batch_max_bytes
+        {
+            self.batch_full.notify_one();
+        }
+    }
 
-        BlobClient::blob_submit(&self.da_client, &[blob], TxConfig::default()).await?;
+    /// Updates the mempool size metric, byte count and the height of the
+    /// oldest pending transaction after transactions were removed from or
+    /// put back into the mempool.
+    fn mempool_changed(&self, pending_txs: &[PendingTx]) {
+        self.metrics.mempool_size.set(pending_txs.len() as i64);
+        self.pending_bytes.store(
+            pending_txs
+                .iter()
+                .map(|pending| encoded_size(&pending.tx))
+                .sum(),
+            Ordering::Release,
+        );
+        let pending_since = pending_txs.iter().map(|pending| pending.queued_at).min();
+        self.pending_since
+            .store(pending_since.unwrap_or_default(), Ordering::Release);
+    }
+
+    /// Removes transactions that were included on-chain from the mempool, so
+    /// they are not posted again. The remaining transactions keep the height
+    /// they were queued at, so a poster including only some of them does not
+    /// postpone failover.
+    async fn prune_pending(&self, included: &HashSet<String>) {
+        let mut pending_txs = self.pending_transactions.lock().await;
+        let len = pending_txs.len();
+        pending_txs.retain(|pending| !included.contains(&pending.tx.hash()));
+        if pending_txs.len() == len {
+            return;
+        }
 
-        Ok(batch)
+        debug!(
+            "removed {} transactions included on-chain from mempool",
+            len - pending_txs.len()
+        );
+        self.mempool_changed(&pending_txs);
     }
 
-    async fn process_l1_block(&self, blobs: Vec<Blob>) {
-        let txs: Vec<Transaction> = blobs
-            .into_iter()
-            .flat_map(|blob| {
-                Batch::try_from(&blob)
-                    .map(|b| b.get_transactions())
-                    .unwrap_or_default()
+    /// Returns true if this node may post a batch now, according to the
+    /// posting schedule.
+    fn is_posting_slot(&self) -> bool {
+        let Some(schedule) = &self.schedule else {
+            return true;
+        };
+
+        let height = self.metrics.network_head.get() as u64;
+        let waited = height.saturating_sub(self.pending_since.load(Ordering::Acquire));
+        let may_post = schedule.may_post(height, waited);
+        if !may_post {
+            debug!(
+                "slot at height {} belongs to {}, not posting",
+                height,
+                schedule.slot_owner(height)
+            );
+        }
+        may_post
+    }
+
+    /// Validates a transaction against the current state and the blob size
+    /// limit, without queuing it.
+    pub async fn validate_transaction(&self, tx: &Transaction) -> Result<()> {
+        let tx_size = bincode::serialized_size(tx)? as usize;
+        if tx_size + BLOB_OVERHEAD > self.cfg.max_blob_size {
+            bail!(
+                "transaction of {} bytes does not fit into a blob of at most {} bytes",
+                tx_size,
+                self.cfg.max_blob_size
+            );
+        }
+        self.state.lock().await.validate_tx(tx.clone())
+    }
+
+    /// Returns true if all submission slots are busy and the mempool already
+    /// holds a full batch, so new transactions are rejected until submissions
+    /// complete.
+    pub async fn is_congested(&self) -> bool {
+        self.cfg.role == NodeRole::Sequencer
+            && self.submission_slots.available_permits() == 0
+            && self.pending_transactions.lock().await.len() >= self.cfg.batch_max_txs
+    }
+
+    /// Returns true once historical sync has completed and the node is
+    /// processing incoming blocks.
+    pub fn is_synced(&self) -> bool {
+        self.genesis_synced.load(Ordering::Acquire)
+    }
+
+    /// Returns true if at least one Celestia node responds to requests.
+    pub fn is_da_connected(&self) -> bool {
+        self.da_client.is_healthy()
+    }
+
+    pub async fn status(&self) -> NodeStatus {
+        let last_processed_height = self.metrics.da_height.get() as u64;
+        NodeStatus {
+            namespace: namespace_to_hex(&self.cfg.namespace),
+            start_height: self.cfg.start_height,
+            last_processed_height,
+            network_head: self.metrics.network_head.get() as u64,
+            mempool_size: self.pending_transactions.lock().await.len(),
+            synced: self.is_synced(),
+            batching_paused: self.is_batching_paused(),
+            role: self.cfg.role,
+            protocol_version: forks::fork_at(last_processed_height).version,
+        }
+    }
+
+    /// Returns up to `limit` rejected blobs and transactions from
+    /// `from_height` on, oldest first.
+    pub async fn dead_letters(&self, from_height: u64, limit: usize) -> Vec<DeadLetter> {
+        self.dead_letters.list(from_height, limit).await
+    }
+
+    pub(crate) fn admin_token(&self) -> Option<&str> {
+        self.cfg.admin_token.as_deref()
+    }
+
+    pub fn pause_batching(&self) {
+        info!("batch posting paused");
+        self.batching_paused.store(true, Ordering::Release);
+    }
+
+    pub fn resume_batching(&self) {
+        info!("batch posting resumed");
+        self.batching_paused.store(false, Ordering::Release);
+    }
+
+    pub fn is_batching_paused(&self) -> bool {
+        self.batching_paused.load(Ordering::Acquire)
+    }
+
+    pub fn batch_interval(&self) -> Duration {
+        Duration::from_millis(self.batch_interval_ms.load(Ordering::Acquire))
+    }
+
+    /// Changes the batch interval, taking effect after the current interval.
+    pub fn set_batch_interval(&self, interval: Duration) {
+        info!("batch interval set to {:?}", interval);
+        self.batch_interval_ms
+            .store(interval.as_millis() as u64, Ordering::Release);
+    }
+
+    /// Starts posting the pending transactions immediately, even if batch
+    /// posting is paused. Returns the number of transactions submitted.
+    pub async fn flush_batch(self: &Arc<Self>) -> Result<usize> {
+        if self.cfg.role == NodeRole::Follower {
+            bail!("follower nodes do not post batches");
+        }
+        self.post_pending_batch().await.inspect_err(|_| {
+            self.metrics.batch_post_failures.inc();
+        })
+    }
+
+    pub async fn mempool(&self) -> Vec<MempoolEntry> {
+        self.pending_transactions
+            .lock()
+            .await
+            .iter()
+            .map(|pending| MempoolEntry {
+                tx_hash: pending.tx.hash(),
+                sender: pending.tx.sender(),
+                nonce: pending.tx.nonce,
             })
-            .collect();
+            .collect()
+    }
 
-        let mut state = self.state.lock().await;
+    /// Removes a queued transaction by hash. Returns false if it was not
+    /// found.
+    pub async fn drop_transaction(&self, tx_hash: &str) -> bool {
+        let mut pending_txs = self.pending_transactions.lock().await;
+        let len = pending_txs.len();
+        pending_txs.retain(|pending| pending.tx.hash() != tx_hash);
+        self.mempool_changed(&pending_txs);
+        if pending_txs.len() < len {
+            info!(tx_hash, "dropped transaction from mempool");
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Writes the current state to the snapshot directory and returns the
+    /// path of the snapshot.
+    pub async fn snapshot_state(&self) -> Result<PathBuf> {
+        let dir = self
+            .cfg
+            .snapshot_dir
+            .as_ref()
+            .context("No snapshot directory configured")?;
+
+        let state = self.state.lock().await;
+        let da_height = self.metrics.da_height.get() as u64;
+        let encoded = bincode::serialize(&StateSnapshot {
+            da_height,
+            state: &state,
+        })?;
+        drop(state);
+
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        let path = dir.join(format!("state-{}.bin", da_height));
+        fs::write(&path, encoded)
+            .with_context(|| format!("Failed to write snapshot to {}", path.display()))?;
+        info!(
+            "wrote state snapshot at height {} to {}",
+            da_height,
+            path.display()
+        );
+        Ok(path)
+    }
+
+    /// Drains the mempool and starts submitting it as one or more blobs,
+    /// without waiting for their inclusion. Returns the number of
+    /// transactions handed to submission.
+    ///
+    /// Up to [`Config::max_inflight_submissions`] batches are in flight at
+    /// once, so batches may be submitted and included in a different order
+    /// than they were created. Signed batches are executed by batch number,
+    /// so their order is kept, while unsigned blobs are executed in the order
+    /// they were included. The blobs of a split batch are submitted one after
+    /// another, so they are always included in order.
+    #[instrument(skip_all)]
+    async fn post_pending_batch(self: &Arc<Self>) -> Result<usize> {
+        if self.batch_signer.is_some() && !self.is_synced() {
+            bail!("batches can only be signed after historical sync completed");
+        }
+        // wait for a free slot before draining, so the mempool fills up and
+        // pushes back on new transactions while all slots are busy
+        let permit = self.submission_slots.clone().acquire_owned().await?;
+
+        let txs: Vec<Transaction> = {
+            let mut pending_txs = self.pending_transactions.lock().await;
+            if pending_txs.is_empty() {
+                return Ok(0);
+            }
+            let txs = pending_txs.drain(..).map(|pending| pending.tx).collect();
+            self.mempool_changed(&pending_txs);
+            txs
+        };
+
+        let chunks = split_batch(
+            txs,
+            encoded_size,
+            self.cfg.max_blob_size,
+            self.cfg.max_txs_per_blob,
+        );
+        if chunks.len() > 1 {
+            info!("splitting batch into {} blobs", chunks.len());
+        }
+
+        let mut blobs = Vec::with_capacity(chunks.len());
+        let mut encode_res = Ok(());
+        let mut chunks = chunks.into_iter();
+        while let Some(chunk) = chunks.next() {
+            match self.encode_blob(&chunk).await {
+                Ok((blob, batch_number)) => blobs.push(PendingBlob {
+                    blob,
+                    txs: chunk,
+                    batch_number,
+                }),
+                Err(e) => {
+                    // blobs encoded so far are still submitted, as signed
+                    // batches already advanced the posted head
+                    self.requeue(std::iter::once(chunk).chain(chunks).flatten().collect())
+                        .await;
+                    encode_res = Err(e);
+                    break;
+                }
+            }
+        }
+
+        let tx_count = blobs.iter().map(|pending| pending.txs.len()).sum();
+        if !blobs.is_empty() {
+            self.metrics.submissions_in_flight.inc();
+            let node = self.clone();
+            tokio::spawn(async move {
+                node.submit_blobs(blobs).await;
+                node.metrics.submissions_in_flight.dec();
+                drop(permit);
+            });
+        }
+
+        encode_res.map(|()| tx_count)
+    }
+
+    /// Encodes transactions into a blob, signing them as the next batch in
+    /// sequencer mode. Returns the blob and the number of the signed batch.
+    ///
+    /// The signed head advances right away, so the next batch can be signed
+    /// while this one is still being submitted.
+    async fn encode_blob(&self, txs: &[Transaction]) -> Result<(Blob, Option<u64>)> {
         for tx in txs {
-            if let Err(e) = state.process_tx(tx) {
-                error!("processing tx: {}", e);
+            debug!(tx_hash = %tx.hash(), "adding transaction to blob");
+        }
+        let mut posted_head = self.posted_head.lock().await;
+        let signed = match &self.batch_signer {
+            Some(signer) => Some(self.sign_batch(*posted_head, txs.to_vec(), signer).await?),
+            None => None,
+        };
+        let encoded_batch = match &signed {
+            Some(signed) => envelope::seal(
+                PayloadKind::SignedBatch,
+                &bincode::serialize(signed)?,
+                self.cfg.compression,
+            )?,
+            None => envelope::seal(
+                PayloadKind::Batch,
+                &bincode::serialize(&Batch::new(txs.to_vec()))?,
+                self.cfg.compression,
+            )?,
+        };
+        self.metrics.blob_size.observe(encoded_batch.len() as f64);
+        // batch payloads never pass the signer on to their transactions
+        let blob = match &self.blob_signer {
+            Some(signer) => Blob::new_with_signer(
+                self.cfg.namespace,
+                encoded_batch,
+                signer.clone(),
+                APP_VERSION,
+            )?,
+            None => Blob::new(self.cfg.namespace, encoded_batch, APP_VERSION)?,
+        };
+
+        let batch_number = signed.map(|signed| {
+            *posted_head = Some((signed.batch_number + 1, signed.hash()));
+            signed.batch_number
+        });
+        Ok((blob, batch_number))
+    }
+
+    /// Signs a batch as the successor of `posted_head`, or of the inbox head
+    /// if this node has not signed a batch since it started.
+    async fn sign_batch(
+        &self,
+        posted_head: Option<(u64, BatchHash)>,
+        transactions: Vec<Transaction>,
+        signer: &SigningKey,
+    ) -> Result<SignedBatch> {
+        let (batch_number, prev_batch_hash) = match posted_head {
+            Some(head) => head,
+            None => self
+                .inbox
+                .as_ref()
+                .context("not in sequencer mode")?
+                .lock()
+                .await
+                .head(),
+        };
+        SignedBatch::new(batch_number, prev_batch_hash, transactions, signer)
+    }
+
+    /// Submits the blobs of a batch one after another, so a split batch is
+    /// included in order. If a blob is not included, its transactions and
+    /// those of the following blobs are put back into the mempool for a
+    /// later batch.
+    async fn submit_blobs(&self, blobs: Vec<PendingBlob>) {
+        let mut blobs = blobs.into_iter();
+        while let Some(pending) = blobs.next() {
+            if !self.submit_blob(&pending.blob, pending.batch_number).await {
+                let txs = std::iter::once(pending.txs)
+                    .chain(blobs.map(|pending| pending.txs))
+                    .flatten()
+                    .collect();
+                self.requeue(txs).await;
+                return;
+            }
+        }
+    }
+
+    /// Submits a blob until it is included. An unsigned blob is given up
+    /// after the first failure. Signed batches are resubmitted until
+    /// shutdown instead, as the batches signed after them can only be
+    /// executed once they are included. Returns false if the blob was not
+    /// included.
+    async fn submit_blob(&self, blob: &Blob, batch_number: Option<u64>) -> bool {
+        loop {
+            let Err(e) = self.try_submit_blob(blob).await else {
+                if let Some(batch_number) = batch_number {
+                    debug!(batch_number, "signed batch included");
+                }
+                return true;
+            };
+            self.metrics.batch_post_failures.inc();
+            error!("submitting blob: {}", e);
+
+            let Some(batch_number) = batch_number else {
+                return false;
+            };
+            tokio::select! {
+                _ = tokio::time::sleep(SIGNED_BATCH_RETRY_DELAY) => {
+                    warn!(batch_number, "resubmitting signed batch");
+                }
+                _ = self.shutdown.cancelled() => {
+                    error!(
+                        batch_number,
+                        "signed batch was not included before shutdown, later batches can not be executed"
+                    );
+                    return false;
+                }
+            }
+        }
+    }
+
+    /// Submits a blob once and returns the height it was included at.
+    async fn try_submit_blob(&self, blob: &Blob) -> Result<u64> {
+        let started = Instant::now();
+        let tx_config = fees::tx_config(&self.cfg, blob.data.len());
+        let response =
+            self.da_client
+                .request(|client| {
+                    let blobs = [blob.clone()];
+                    let tx_config = tx_config.clone();
+                    async move {
+                        StateClient::state_submit_pay_for_blob(&*client, &blobs, tx_config).await
+                    }
+                })
+                .await?;
+        self.metrics
+            .batch_post_latency
+            .observe(started.elapsed().as_secs_f64());
+
+        let height = response.height.value();
+        let gas_price = fees::gas_price(&self.cfg);
+        let fee = fees::fee(gas_price, response.gas_wanted);
+        self.metrics.batch_gas_used.inc_by(response.gas_used as u64);
+        self.metrics.batch_fees.inc_by(fee);
+        info!(
+            da_height = height,
+            celestia_tx_hash = %response.txhash,
+            gas_used = response.gas_used,
+            gas_wanted = response.gas_wanted,
+            gas_price,
+            // the Celestia node may have paid a higher price than the default
+            gas_price_configured = self.cfg.gas_price.is_some(),
+            fee_utia = fee,
+            "blob included"
+        );
+        Ok(height)
+    }
+
+    /// Puts transactions that could not be posted back in front of newer
+    /// transactions in the mempool. They count as queued at the current
+    /// height, as this node tried to post them.
+    async fn requeue(&self, txs: Vec<Transaction>) {
+        let queued_at = self.metrics.network_head.get() as u64;
+        let mut pending_txs = self.pending_transactions.lock().await;
+        pending_txs.splice(0..0, txs.into_iter().map(|tx| PendingTx { tx, queued_at }));
+        self.mempool_changed(&pending_txs);
+        self.batch_started.notify_one();
+    }
+
+    /// Waits until all blob submissions in flight have completed.
+    async fn wait_for_submissions(&self) {
+        let in_flight = self.metrics.submissions_in_flight.get();
+        if in_flight > 0 {
+            info!("waiting for {} blob submissions to complete", in_flight);
+        }
+        let all_slots = self.cfg.max_inflight_submissions as u32;
+        let _ = self.submission_slots.acquire_many(all_slots).await;
+    }
+
+    #[instrument(skip(self, blobs), fields(da_height = height))]
+    async fn process_l1_block(&self, height: u64, blobs: Vec<Blob>) {
+        let mut processed = ProcessedBlock {
+            da_height: height,
+            tx_count: 0,
+            failed_tx_count: 0,
+        };
+
+        let fork = forks::fork_at(height);
+        if fork.activation_height == height && height > 0 {
+            info!(
+                "activating fork {} (protocol version {})",
+                fork.name, fork.version
+            );
+        }
+
+        let InboxOutput {
+            batches,
+            rejected: mut dead_letters,
+            orphaned,
+        } = match &self.inbox {
+            Some(inbox) => inbox
+                .lock()
+                .await
+                .process_block(height, fork.version, &blobs),
+            None => {
+                let mut batches = Vec::new();
+                let mut rejected = Vec::new();
+                for (blob_index, blob) in blobs.iter().enumerate() {
+                    let blob_ref = BlobRef::new(height, blob_index, blob);
+                    match forks::decode_blob(fork.version, blob) {
+                        Ok(payload) => batches.push(InboxBatch {
+                            blob: blob_ref,
+                            transactions: payload.into_transactions(),
+                        }),
+                        Err(e) => rejected.push(DeadLetter::blob(
+                            blob_ref,
+                            format!("undecodable blob: {:#}", e),
+                        )),
+                    }
+                }
+                InboxOutput {
+                    batches,
+                    rejected,
+                    orphaned: Vec::new(),
+                }
+            }
+        };
+
+        let mut included = HashSet::new();
+        let mut state = self.state.lock().await;
+        state.begin_block(fork.version);
+
+        for batch in batches {
+            let _blob_span = info_span!("blob", blob_index = batch.blob.blob_index).entered();
+            for tx in batch.transactions {
+                let tx_hash = tx.hash();
+                let _tx_span = info_span!("tx", tx_hash = %tx_hash).entered();
+                included.insert(tx_hash.clone());
+                let sender = tx.sender();
+                match state.process_tx(tx) {
+                    Ok(()) => {
+                        processed.tx_count += 1;
+                        self.metrics.txs_processed.inc();
+                        debug!("processed tx from {}", sender);
+                    }
+                    Err(e) => {
+                        processed.failed_tx_count += 1;
+                        self.metrics.txs_failed.inc();
+                        error!("processing tx from {}: {}", sender, e);
+                        dead_letters.push(DeadLetter::transaction(batch.blob.clone(), tx_hash, e));
+                    }
+                }
             }
         }
+
+        // set while the state is locked, so snapshots pair the state with
+        // the height it was applied up to
+        self.metrics.da_height.set(height as i64);
+        drop(state);
+
+        for letter in dead_letters {
+            if self.dead_letters.record(letter).await {
+                self.metrics.dead_letters.inc();
+            }
+        }
+        if !orphaned.is_empty() {
+            self.requeue_orphaned(orphaned).await;
+        }
+
+        if !included.is_empty() {
+            self.prune_pending(&included).await;
+            if let Some(gossip) = &self.gossip {
+                for tx_hash in included {
+                    gossip.mark_seen(tx_hash).await;
+                }
+            }
+        }
+
+        self.metrics.blocks_processed.inc();
+        // only fails if there are no subscribers
+        let _ = self.processed_blocks.send(processed);
+    }
+
+    /// Puts the transactions of this node's signed batches that lost their
+    /// batch number to another batch back into the mempool, e.g. batches
+    /// that were in flight when the node restarted and were signed again.
+    async fn requeue_orphaned(&self, orphaned: Vec<SignedBatch>) {
+        if self.batch_signer.is_none() {
+            return;
+        }
+        // batches signed on top of an orphaned batch are orphaned as well, so
+        // continue signing from the inbox head
+        *self.posted_head.lock().await = None;
+        // batches orphaned before the restart were requeued back then
+        if !self.is_synced() {
+            return;
+        }
+
+        let batch_numbers: Vec<u64> = orphaned.iter().map(|batch| batch.batch_number).collect();
+        let txs: Vec<Transaction> = orphaned
+            .into_iter()
+            .flat_map(|batch| batch.transactions)
+            .collect();
+        warn!(
+            "requeueing {} transactions of orphaned batches {:?}",
+            txs.len(),
+            batch_numbers
+        );
+        self.requeue(txs).await;
     }
 
     async fn sync_historical(&self) -> Result<()> {
-        let network_head = HeaderClient::header_network_head(&self.da_client).await?;
+        let network_head = self
+            .da_client
+            .request(|client| async move { HeaderClient::header_network_head(&*client).await })
+            .await?;
         let network_height = network_head.height();
+        self.metrics.network_head.set(network_height.value() as i64);
         info!(
             "syncing historical blocks from {}-{}",
             self.cfg.start_height,
@@ -132,117 +1302,690 @@ impl Node {
         );
 
         for height in self.cfg.start_height..network_height.value() {
-            let blobs =
-                BlobClient::blob_get_all(&self.da_client, height, &[self.cfg.namespace]).await?;
-            if let Some(blobs) = blobs {
-                self.process_l1_block(blobs).await;
-            }
+            let blobs = self.fetch_blobs(height).await?;
+            self.process_l1_block(height, blobs).await;
         }
 
         info!("historical sync completed");
+        self.genesis_synced.store(true, Ordering::Release);
         self.genesis_sync_completed.notify_one();
 
         Ok(())
     }
 
+    /// Processes the blocks of the blob subscription. If the subscription
+    /// ends, it is moved to the next Celestia node, and heights missed in the
+    /// meantime are fetched before continuing.
     async fn sync_incoming_blocks(&self) -> Result<()> {
-        let mut blobsub = BlobClient::blob_subscribe(&self.da_client, self.cfg.namespace)
+        let mut blobsub = self
+            .da_client
+            .subscribe(self.cfg.namespace)
             .await
             .context("Failed to subscribe to app namespace")?;
 
         self.genesis_sync_completed.notified().await;
 
-        while let Some(result) = blobsub.next().await {
-            match result {
-                Ok(blob_response) => {
-                    info!(
-                        "processing incoming celestia height: {}",
-                        blob_response.height
-                    );
-                    if let Some(blobs) = blob_response.blobs {
-                        self.process_l1_block(blobs).await;
+        loop {
+            let Some(result) = blobsub.next().await else {
+                warn!("blob subscription ended, subscribing again");
+                loop {
+                    match self.da_client.subscribe(self.cfg.namespace).await {
+                        Ok(subscription) => {
+                            blobsub = subscription;
+                            break;
+                        }
+                        Err(e) => error!("subscribing to app namespace: {}", e),
                     }
+                    tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+                }
+                continue;
+            };
+
+            let blob_response = match result {
+                Ok(blob_response) => blob_response,
+                Err(e) => {
+                    error!("retrieving blobs from DA layer: {}", e);
+                    continue;
                 }
-                Err(e) => error!("retrieving blobs from DA layer: {}", e),
+            };
+            let height = blob_response.height;
+            let next_height = self.next_height();
+            if height < next_height {
+                debug!("skipping already processed celestia height {}", height);
+                continue;
+            }
+            for missed in next_height..height {
+                info!("processing missed celestia height: {}", missed);
+                let blobs = self.fetch_blobs(missed).await?;
+                self.process_l1_block(missed, blobs).await;
             }
+
+            info!("processing incoming celestia height: {}", height);
+            self.metrics.network_head.set(height as i64);
+            let blobs = if self.cfg.cross_check_reads {
+                self.da_client.get_blobs(height, self.cfg.namespace).await?
+            } else {
+                blob_response.blobs.unwrap_or_default()
+            };
+            let blobs = self.verify_blobs(height, blobs).await?;
+            self.process_l1_block(height, blobs).await;
         }
-        Ok(())
     }
 
-    async fn sync(self: Arc<Self>) -> Result<()> {
-        let genesis_sync = {
-            let node = self.clone();
-            tokio::spawn(async move { node.sync_historical().await })
-        };
+    /// Fetches the blobs of the rollup namespace at `height`, verified if
+    /// [`Config::verify_proofs`] is set.
+    async fn fetch_blobs(&self, height: u64) -> Result<Vec<Blob>> {
+        let blobs = self.da_client.get_blobs(height, self.cfg.namespace).await?;
+        self.verify_blobs(height, blobs).await
+    }
 
-        let incoming_sync = {
-            let node = self.clone();
-            tokio::spawn(async move { node.sync_incoming_blocks().await })
-        };
+    /// Verifies the blobs of the rollup namespace at `height` with namespace
+    /// proofs if [`Config::verify_proofs`] is set, and returns the blobs
+    /// reconstructed from the proven shares, see [`proofs::verify_namespace`].
+    ///
+    /// The header of `height` is verified against the last verified header,
+    /// or against the configured [`TrustedHeader`] if there is none yet.
+    async fn verify_blobs(&self, height: u64, blobs: Vec<Blob>) -> Result<Vec<Blob>> {
+        if !self.cfg.verify_proofs {
+            return Ok(blobs);
+        }
+        let trusted = self
+            .cfg
+            .trusted_header
+            .as_ref()
+            .context("No trusted header configured")?;
+        if height < trusted.height {
+            bail!(
+                "height {} is below the trusted header at height {}",
+                height,
+                trusted.height
+            );
+        }
 
-        let res = tokio::join!(genesis_sync, incoming_sync);
+        let mut verified_header = self.verified_header.lock().await;
+        let header = self.get_header(height).await?;
+        if height == trusted.height {
+            trusted.check(&header)?;
+        } else {
+            let last_verified = match verified_header
+                .take()
+                .filter(|verified| verified.height().value() < height)
+            {
+                Some(verified) => verified,
+                None => {
+                    let trusted_header = self.get_header(trusted.height).await?;
+                    trusted.check(&trusted_header)?;
+                    trusted_header
+                }
+            };
+            // also verifies headers that are not adjacent, as long as enough
+            // of the validators that signed the last verified header signed
+            // this one
+            last_verified.verify(&header).with_context(|| {
+                format!(
+                    "Header at height {} can not be verified against height {}",
+                    height,
+                    last_verified.height()
+                )
+            })?;
+        }
 
-        match res {
-            (Ok(Ok(_)), Ok(Ok(_))) => Ok(()),
-            (Err(e), _) | (_, Err(e)) => Err(anyhow::anyhow!("Task join error: {}", e)),
-            (Ok(Err(e)), _) | (_, Ok(Err(e))) => Err(e),
+        let namespace = self.cfg.namespace;
+        let data = self
+            .da_client
+            .request(|client| async move {
+                ShareClient::share_get_namespace_data(&*client, height, namespace).await
+            })
+            .await?;
+        let proven = proofs::verify_namespace(&header, namespace, &data, &blobs)
+            .with_context(|| format!("Failed to verify blobs at height {}", height))?;
+        debug!("verified {} blobs at height {}", proven.len(), height);
+        *verified_header = Some(header);
+        Ok(proven)
+    }
+
+    async fn get_header(&self, height: u64) -> Result<ExtendedHeader> {
+        self.da_client
+            .request(
+                |client| async move { HeaderClient::header_get_by_height(&*client, height).await },
+            )
+            .await
+    }
+
+    /// Returns the next Celestia height to process.
+    fn next_height(&self) -> u64 {
+        let da_height = self.metrics.da_height.get() as u64;
+        (da_height + 1).max(self.cfg.start_height)
+    }
+
+    /// Periodically checks the Celestia nodes, reconnecting to those that
+    /// went down.
+    async fn monitor_da_health(&self) -> Result<()> {
+        loop {
+            let healthy = self.da_client.check_health().await;
+            self.metrics.da_nodes_healthy.set(healthy as i64);
+            tokio::select! {
+                _ = tokio::time::sleep(DA_HEALTH_CHECK_INTERVAL) => {}
+                _ = self.shutdown.cancelled() => return Ok(()),
+            }
         }
     }
 
-    async fn start_batch_posting(&self) -> Result<()> {
+    /// Syncs historical and incoming blocks concurrently. Both run inside
+    /// the returned future, so dropping it on shutdown stops them.
+    async fn sync(&self) -> Result<()> {
+        tokio::try_join!(self.sync_historical(), self.sync_incoming_blocks())?;
+        Ok(())
+    }
+
+    /// Posts a batch once the oldest pending transaction has waited for the
+    /// batch interval, or as soon as the mempool is full. While the mempool is
+    /// empty, no timer runs at all.
+    async fn start_batch_posting(self: &Arc<Self>) -> Result<()> {
         loop {
-            tokio::time::sleep(self.cfg.batch_interval).await;
-            match self.post_pending_batch().await {
-                Ok(batch) => {
-                    let tx_count = batch.get_transactions().len();
-                    if tx_count > 0 {
-                        info!("batch posted with {} transactions", tx_count);
-                    } else {
-                        debug!("no transactions to post, skipping batch");
-                    }
+            if self.pending_transactions.lock().await.is_empty() {
+                tokio::select! {
+                    // check again, the notification may be left over from an earlier batch
+                    _ = self.batch_started.notified() => continue,
+                    _ = self.shutdown.cancelled() => return self.post_final_batch().await,
+                }
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(self.batch_interval()) => {}
+                _ = self.batch_full.notified() => debug!("mempool is full, posting batch early"),
+                _ = self.shutdown.cancelled() => return self.post_final_batch().await,
+            }
+            if self.is_batching_paused() {
+                debug!("batch posting is paused, skipping batch");
+                continue;
+            }
+            if !self.is_posting_slot() {
+                continue;
+            }
+            if self.batch_signer.is_some() && !self.is_synced() {
+                debug!("waiting for historical sync before signing batches");
+                continue;
+            }
+            self.post_and_log_batch().await;
+        }
+    }
+
+    async fn post_final_batch(self: &Arc<Self>) -> Result<()> {
+        if self.is_batching_paused() {
+            warn!("batch posting is paused, not posting final batch");
+        } else if !self.is_posting_slot() {
+            info!("not this node's posting slot, leaving final batch to other posters");
+        } else {
+            info!("posting final batch before shutdown");
+            self.post_and_log_batch().await;
+        }
+        self.wait_for_submissions().await;
+        Ok(())
+    }
+
+    async fn post_and_log_batch(self: &Arc<Self>) {
+        match self.post_pending_batch().await {
+            Ok(tx_count) => {
+                if tx_count > 0 {
+                    info!("submitting batch with {} transactions", tx_count);
+                } else {
+                    debug!("no transactions to post, skipping batch");
                 }
-                Err(e) => error!("posting batch: {}", e),
+            }
+            Err(e) => {
+                self.metrics.batch_post_failures.inc();
+                error!("posting batch: {}", e);
             }
         }
     }
 
     pub async fn start_server(self: Arc<Self>) -> Result<()> {
-        let app = Router::new()
-            .route("/submit_tx", post(submit_tx))
+        let mut app = Router::new();
+        if self.cfg.role == NodeRole::Sequencer {
+            app = app.route("/submit_tx", post(submit_tx));
+        } else if self.forwarder.is_some() {
+            app = app.route("/submit_tx", post(forward_tx));
+        } else if self.gossip.is_some() {
+            app = app.route("/submit_tx", post(submit_tx));
+        }
+
+        if self.gossip.is_some() {
+            app = app.route("/gossip/tx", post(gossip_tx));
+        }
+
+        let app = app
+            .route("/metrics", get(metrics))
+            .route("/health", get(health))
+            .route("/ready", get(ready))
+            .route("/status", get(status))
+            .route("/dead_letters", get(dead_letters))
+            .layer(
+                ServiceBuilder::new()
+                    .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+                    .layer(TraceLayer::new_for_http().make_span_with(make_request_span))
+                    .layer(PropagateRequestIdLayer::x_request_id()),
+            )
             .with_state(self.clone());
 
         let listen_addr = self.cfg.listen_addr.clone();
         info!("webserver listening on {}", listen_addr);
-        axum::Server::bind(&listen_addr.parse().unwrap())
+        axum::Server::bind(&listen_addr.parse().context("Invalid listen address")?)
             .serve(app.into_make_service())
+            .with_graceful_shutdown(self.shutdown.cancelled())
             .await
             .context("Failed to start server")
     }
 
-    pub async fn start(self: Arc<Self>) -> Result<()> {
-        let sync_handle = self.clone().sync();
+    pub async fn start_admin_server(self: Arc<Self>, listen_addr: String) -> Result<()> {
+        let app = admin::router(self.clone());
+        info!("admin API listening on {}", listen_addr);
+        axum::Server::bind(
+            &listen_addr
+                .parse()
+                .context("Invalid admin listen address")?,
+        )
+        .serve(app.into_make_service())
+        .with_graceful_shutdown(self.shutdown.cancelled())
+        .await
+        .context("Failed to start admin server")
+    }
+
+    /// Spawns the node's tasks and returns a handle to control it.
+    pub fn spawn(self: Arc<Self>) -> NodeHandle {
+        let mut tasks = JoinSet::new();
+
+        let node = self.clone();
+        tasks.spawn(async move {
+            // syncing has nothing to flush, so it is dropped right away
+            let res = tokio::select! {
+                res = node.sync() => res,
+                _ = node.shutdown.cancelled() => Ok(()),
+            };
+            ("sync", res)
+        });
 
-        let webserver = {
+        let node = self.clone();
+        tasks.spawn(async move { ("webserver", node.start_server().await) });
+
+        let node = self.clone();
+        tasks.spawn(async move { ("da health", node.monitor_da_health().await) });
+
+        if self.cfg.role == NodeRole::Sequencer {
             let node = self.clone();
-            tokio::spawn(async move { node.start_server().await })
-        };
+            tasks.spawn(async move { ("batch posting", node.start_batch_posting().await) });
+        }
 
-        let batch_posting = {
+        if let Some(listen_addr) = self.cfg.admin_listen_addr.clone() {
             let node = self.clone();
-            tokio::spawn(async move { node.start_batch_posting().await })
-        };
+            tasks.spawn(async move { ("admin", node.start_admin_server(listen_addr).await) });
+        }
+
+        let node = self.clone();
+        let supervisor = tokio::spawn(async move { node.supervise(tasks).await });
+
+        NodeHandle {
+            node: self,
+            supervisor: Some(supervisor),
+        }
+    }
+
+    /// Runs the node until a task fails or the process receives SIGINT or
+    /// SIGTERM.
+    pub async fn start(self: Arc<Self>) -> Result<()> {
+        let mut handle = self.spawn();
 
         tokio::select! {
-            _ = sync_handle => {
-                error!("sync task exited");
+            res = handle.wait() => return res,
+            _ = shutdown_signal() => info!("received shutdown signal"),
+        }
+
+        handle.shutdown().await
+    }
+
+    /// Waits until a task exits or the node is shut down, then shuts down the
+    /// remaining tasks. A task exiting on its own is returned as an error.
+    async fn supervise(&self, mut tasks: JoinSet<(&'static str, Result<()>)>) -> Result<()> {
+        let res = tokio::select! {
+            biased;
+            _ = self.shutdown.cancelled() => Ok(()),
+            Some(res) = tasks.join_next() => Err(match res {
+                Ok((task, Ok(()))) => anyhow!("{} task exited unexpectedly", task),
+                Ok((task, Err(e))) => e.context(format!("{} task failed", task)),
+                Err(e) => anyhow!("task join error: {}", e),
+            }),
+        };
+
+        if let Err(e) = &res {
+            error!("{:#}", e);
+        }
+        let shutdown_res = self.shutdown(tasks).await;
+        res.and(shutdown_res)
+    }
+
+    /// Stops accepting transactions, waits for the remaining tasks to post
+    /// the final batch and stop the webservers, and persists the mempool and
+    /// state if configured.
+    async fn shutdown(&self, mut tasks: JoinSet<(&'static str, Result<()>)>) -> Result<()> {
+        info!("shutting down");
+        self.shutdown.cancel();
+
+        let drain = async {
+            while let Some(res) = tasks.join_next().await {
+                match res {
+                    Ok((task, Ok(()))) => debug!("{} task stopped", task),
+                    Ok((task, Err(e))) => error!("{} task failed during shutdown: {}", task, e),
+                    Err(e) => error!("task join error: {}", e),
+                }
             }
-            _ = webserver => {
-                error!("webserver task exited");
+        };
+        if tokio::time::timeout(self.cfg.shutdown_timeout, drain)
+            .await
+            .is_err()
+        {
+            warn!(
+                "shutdown timed out after {:?}, aborting remaining tasks",
+                self.cfg.shutdown_timeout
+            );
+            tasks.abort_all();
+        }
+
+        let snapshot_res = match self.cfg.snapshot_dir {
+            Some(_) => self.snapshot_state().await.map(drop),
+            None => Ok(()),
+        };
+        // persisted even if the snapshot failed, so no transactions are lost
+        let mempool_res = self.persist_mempool().await;
+        match (snapshot_res, mempool_res) {
+            (Err(snapshot_err), Err(mempool_err)) => {
+                error!("writing state snapshot: {:#}", snapshot_err);
+                Err(mempool_err)
             }
-            _ = batch_posting => {
-                error!("batch posting task exited");
+            (snapshot_res, mempool_res) => snapshot_res.and(mempool_res),
+        }
+    }
+
+    /// Writes transactions that were not posted to the configured mempool
+    /// file, so they are queued again on the next start.
+    async fn persist_mempool(&self) -> Result<()> {
+        let pending_txs = self.pending_transactions.lock().await;
+        if pending_txs.is_empty() {
+            return Ok(());
+        }
+
+        match &self.cfg.mempool_file {
+            Some(path) => {
+                let txs: Vec<&Transaction> =
+                    pending_txs.iter().map(|pending| &pending.tx).collect();
+                fs::write(path, bincode::serialize(&txs)?)
+                    .with_context(|| format!("Failed to write mempool to {}", path.display()))?;
+                info!(
+                    "persisted {} pending transactions to {}",
+                    pending_txs.len(),
+                    path.display()
+                );
             }
+            None => warn!(
+                "dropping {} pending transactions, no mempool file configured",
+                pending_txs.len()
+            ),
         }
         Ok(())
     }
 }
+
+/// Handle to a running node, returned by [`Node::spawn`].
+pub struct NodeHandle {
+    node: Arc<Node>,
+    supervisor: Option<JoinHandle<Result<()>>>,
+}
+
+impl NodeHandle {
+    pub fn node(&self) -> &Arc<Node> {
+        &self.node
+    }
+
+    pub async fn status(&self) -> NodeStatus {
+        self.node.status().await
+    }
+
+    /// Validates and queues a transaction for the next batch. Returns the
+    /// transaction hash.
+    pub async fn submit(&self, tx: Transaction) -> Result<String> {
+        let tx_hash = tx.hash();
+        self.node.queue_transaction(tx).await?;
+        Ok(tx_hash)
+    }
+
+    /// Runs `f` with read access to the current state.
+    pub async fn query_state<R>(&self, f: impl FnOnce(&State) -> R) -> R {
+        f(&*self.node.state.lock().await)
+    }
+
+    /// Returns a stream of notifications for each processed Celestia block.
+    /// Notifications are dropped for subscribers that fall too far behind.
+    pub fn processed_blocks(&self) -> impl Stream<Item = ProcessedBlock> {
+        BroadcastStream::new(self.node.processed_blocks.subscribe())
+            .filter_map(|notification| notification.ok())
+    }
+
+    /// Waits until the node stops, returning an error if one of its tasks
+    /// failed.
+    pub async fn wait(&mut self) -> Result<()> {
+        let Some(supervisor) = &mut self.supervisor else {
+            return Ok(());
+        };
+        let res = supervisor.await;
+        self.supervisor = None;
+        res.context("Node supervisor panicked")?
+    }
+
+    /// Shuts the node down gracefully and waits for it to stop.
+    pub async fn shutdown(mut self) -> Result<()> {
+        self.node.shutdown.cancel();
+        self.wait().await
+    }
+}
+
+/// Loads transactions persisted by a previous shutdown and removes the file,
+/// so they are not queued twice.
+fn load_mempool(path: &Path) -> Result<Vec<Transaction>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = fs::read(path)
+        .with_context(|| format!("Failed to read mempool from {}", path.display()))?;
+    let txs: Vec<Transaction> = bincode::deserialize(&contents)
+        .with_context(|| format!("Failed to decode mempool from {}", path.display()))?;
+    fs::remove_file(path)?;
+    info!(
+        "loaded {} pending transactions from {}",
+        txs.len(),
+        path.display()
+    );
+    Ok(txs)
+}
+
+/// Returns the bincode encoded size of a transaction.
+fn encoded_size(tx: &Transaction) -> usize {
+    bincode::serialized_size(tx).unwrap_or_default() as usize
+}
+
+/// Splits transactions into consecutive chunks that each fit into a single
+/// blob, keeping their order. `encoded_size` returns the size of a
+/// transaction in the blob.
+fn split_batch<T>(
+    txs: Vec<T>,
+    encoded_size: impl Fn(&T) -> usize,
+    max_blob_size: usize,
+    max_txs_per_blob: usize,
+) -> Vec<Vec<T>> {
+    let mut chunks = Vec::new();
+    let mut chunk = Vec::new();
+    let mut chunk_size = BLOB_OVERHEAD;
+    for tx in txs {
+        let tx_size = encoded_size(&tx);
+        if !chunk.is_empty()
+            && (chunk.len() >= max_txs_per_blob || chunk_size + tx_size > max_blob_size)
+        {
+            chunks.push(std::mem::take(&mut chunk));
+            chunk_size = BLOB_OVERHEAD;
+        }
+        chunk_size += tx_size;
+        chunk.push(tx);
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+    chunks
+}
+
+/// Resolves once the process receives SIGINT or SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("listening for ctrl-c: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!("listening for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+/// Hex encodes the id of a v0 namespace, without the zero padding added by
+/// [`Namespace::new_v0`].
+pub fn namespace_to_hex(namespace: &Namespace) -> String {
+    let id = &namespace.as_bytes()[19..];
+    let start = id.iter().position(|b| *b != 0).unwrap_or(id.len() - 1);
+    hex::encode(&id[start..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    const TX_SIZE: usize = 100;
+
+    /// Splits `count` transactions of [`TX_SIZE`] bytes, identified by their
+    /// position.
+    fn split(count: usize, max_blob_size: usize, max_txs_per_blob: usize) -> Vec<Vec<usize>> {
+        split_batch(
+            (0..count).collect(),
+            |_| TX_SIZE,
+            max_blob_size,
+            max_txs_per_blob,
+        )
+    }
+
+    #[test]
+    fn split_batch_fills_blobs_in_order() {
+        let chunks = split(5, BLOB_OVERHEAD + 2 * TX_SIZE, 100);
+        assert_eq!(chunks, [vec![0, 1], vec![2, 3], vec![4]]);
+    }
+
+    #[test]
+    fn split_batch_posts_oversized_transactions_alone() {
+        // callers reject such transactions on submission, but a blob is
+        // never left empty
+        let chunks = split(3, BLOB_OVERHEAD + TX_SIZE - 1, 100);
+        assert_eq!(chunks, [vec![0], vec![1], vec![2]]);
+    }
+
+    #[test]
+    fn split_batch_with_one_transaction_per_blob() {
+        let chunks = split(3, DEFAULT_MAX_BLOB_SIZE, 1);
+        assert_eq!(chunks, [vec![0], vec![1], vec![2]]);
+        assert!(split(0, DEFAULT_MAX_BLOB_SIZE, 1).is_empty());
+    }
+
+    #[test]
+    fn split_batch_measures_encoded_transactions() {
+        let txs = testing::transactions(3);
+        let tx_size = encoded_size(&txs[0]);
+        assert!(tx_size > 0);
+
+        let chunks = split_batch(txs, encoded_size, BLOB_OVERHEAD + 2 * tx_size, 100);
+        let nonces: Vec<Vec<u64>> = chunks
+            .iter()
+            .map(|chunk| chunk.iter().map(|tx| tx.nonce).collect())
+            .collect();
+        assert_eq!(nonces, [vec![0, 1], vec![2]]);
+    }
+
+    fn local_config(port: u16, role: NodeRole, peers: &[u16]) -> Config {
+        let defaults = Config::default();
+        Config {
+            role,
+            listen_addr: format!("127.0.0.1:{}", port),
+            peers: peers
+                .iter()
+                .map(|peer| format!("http://127.0.0.1:{}", peer))
+                .collect(),
+            celestia_urls: std::env::var("SHARD_TEST_CELESTIA_URL")
+                .map(|url| vec![url])
+                .unwrap_or(defaults.celestia_urls.clone()),
+            auth_token: std::env::var("SHARD_TEST_AUTH_TOKEN").ok(),
+            // keep submitted transactions in the mempool during the test
+            batch_interval: Duration::from_secs(600),
+            ..defaults
+        }
+    }
+
+    /// Gossips a transaction submitted to one follower through another
+    /// follower into the sequencer's mempool. The rollup's state has to
+    /// accept the [`testing::transaction`] fixture.
+    #[tokio::test]
+    #[ignore = "needs a local Celestia node, see SHARD_TEST_CELESTIA_URL"]
+    async fn gossips_transactions_between_local_nodes() {
+        let tx = testing::transaction(0);
+
+        // sequencer <-> follower a <-> follower b
+        let mut handles = Vec::new();
+        for cfg in [
+            local_config(39100, NodeRole::Sequencer, &[39101]),
+            local_config(39101, NodeRole::Follower, &[39100, 39102]),
+            local_config(39102, NodeRole::Follower, &[39101]),
+        ] {
+            handles.push(Node::builder().config(cfg).start().await.unwrap());
+        }
+
+        let tx_hash = handles[2].submit(tx).await.unwrap();
+        let sequencer = handles[0].node().clone();
+        let received = tokio::time::timeout(Duration::from_secs(10), async {
+            while !sequencer
+                .mempool()
+                .await
+                .iter()
+                .any(|entry| entry.tx_hash == tx_hash)
+            {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        })
+        .await;
+
+        for handle in handles {
+            handle.shutdown().await.unwrap();
+        }
+        assert!(received.is_ok(), "transaction did not reach the sequencer");
+    }
+}
@@ -0,0 +1,108 @@
+use anyhow::{bail, Result};
+
+/// Deterministic round-robin schedule for nodes sharing a mempool, so that
+/// only one of them posts at a time.
+///
+/// DA heights are split into slots of `slot_length` blocks, and slot `n` is
+/// owned by `posters[n % posters.len()]`. If the owner lags, its slot falls
+/// over to the following posters in order: the `k`-th poster after the owner
+/// may post once the oldest pending transaction has waited
+/// `k * failover_blocks` blocks.
+pub struct PostingSchedule {
+    posters: Vec<String>,
+    own_index: usize,
+    slot_length: u64,
+    failover_blocks: u64,
+}
+
+impl PostingSchedule {
+    pub fn new(
+        posters: Vec<String>,
+        poster_id: &str,
+        slot_length: u64,
+        failover_blocks: u64,
+    ) -> Result<Self> {
+        if slot_length == 0 || failover_blocks == 0 {
+            bail!("slot length and failover blocks must be at least 1");
+        }
+        let Some(own_index) = posters.iter().position(|poster| poster == poster_id) else {
+            bail!("poster id '{}' is not in the list of posters", poster_id);
+        };
+
+        Ok(PostingSchedule {
+            posters,
+            own_index,
+            slot_length,
+            failover_blocks,
+        })
+    }
+
+    /// Returns the id of the poster owning the slot of `height`.
+    pub fn slot_owner(&self, height: u64) -> &str {
+        &self.posters[self.owner_index(height)]
+    }
+
+    /// Returns true if this node may post at `height`, given that the oldest
+    /// pending transaction has waited `waited_blocks` DA blocks.
+    pub fn may_post(&self, height: u64, waited_blocks: u64) -> bool {
+        let n = self.posters.len();
+        let distance = (self.own_index + n - self.owner_index(height)) % n;
+        waited_blocks >= distance as u64 * self.failover_blocks
+    }
+
+    fn owner_index(&self, height: u64) -> usize {
+        ((height / self.slot_length) % self.posters.len() as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(poster_id: &str) -> PostingSchedule {
+        let posters = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        PostingSchedule::new(posters, poster_id, 10, 3).unwrap()
+    }
+
+    #[test]
+    fn rejects_invalid_configs() {
+        let posters = vec!["a".to_string()];
+        assert!(PostingSchedule::new(posters.clone(), "b", 10, 3).is_err());
+        assert!(PostingSchedule::new(posters.clone(), "a", 0, 3).is_err());
+        assert!(PostingSchedule::new(posters, "a", 10, 0).is_err());
+    }
+
+    #[test]
+    fn slots_rotate_between_posters() {
+        let schedule = schedule("a");
+        assert_eq!(schedule.slot_owner(0), "a");
+        assert_eq!(schedule.slot_owner(9), "a");
+        assert_eq!(schedule.slot_owner(10), "b");
+        assert_eq!(schedule.slot_owner(29), "c");
+        assert_eq!(schedule.slot_owner(30), "a");
+    }
+
+    #[test]
+    fn owner_may_post_right_away() {
+        assert!(schedule("a").may_post(5, 0));
+        assert!(schedule("b").may_post(15, 0));
+    }
+
+    #[test]
+    fn other_posters_take_over_in_order() {
+        // slot of "b", followed by "c" and then "a"
+        let c = schedule("c");
+        let a = schedule("a");
+        assert!(!c.may_post(15, 2));
+        assert!(c.may_post(15, 3));
+        assert!(!a.may_post(15, 5));
+        assert!(a.may_post(15, 6));
+    }
+
+    #[test]
+    fn single_poster_always_posts() {
+        let schedule = PostingSchedule::new(vec!["a".to_string()], "a", 10, 3).unwrap();
+        assert!(schedule.may_post(0, 0));
+        assert!(schedule.may_post(123, 0));
+    }
+}
@@ -1,12 +1,19 @@
+use crate::forks::ProtocolVersion;
 use crate::tx::{Transaction, TransactionType};
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 
-#[derive(Default)]
-pub struct State {}
+#[derive(Default, Serialize, Deserialize)]
+pub struct State {
+    /// Protocol version of the block being processed, see [`crate::forks`].
+    pub protocol_version: ProtocolVersion,
+}
 
 impl State {
     pub fn new() -> Self {
-        State {}
+        State {
+            protocol_version: 0,
+        }
     }
 
     /// Validates a transaction against the current chain state.
@@ -26,4 +33,10 @@ impl State {
             TransactionType::Noop => Ok(()),
         }
     }
+
+    /// Called by the node before processing the transactions of each DA
+    /// block.
+    pub(crate) fn begin_block(&mut self, protocol_version: ProtocolVersion) {
+        self.protocol_version = protocol_version;
+    }
 }
@@ -1,8 +1,11 @@
 use anyhow::{anyhow, Context, Result};
-use celestia_types::Blob;
+use celestia_types::{state::AccAddress, Blob};
 use clap::Subcommand;
 use prism_common::keys::{Signature, SigningKey, VerifyingKey};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::address::Address;
 
 /// If true, the system will verify signatures on transactions. If false,
 /// signatures will be ignored.
@@ -30,6 +33,14 @@ pub struct Transaction {
 
     /// Transaction variant.
     pub tx_type: TransactionType,
+
+    /// Celestia account that paid for the blob this transaction was read
+    /// from, if it was posted on its own as a share-version-1 blob. Never set
+    /// for batches, which a sequencer posts on behalf of other users. Only
+    /// set by the node when decoding blobs and never serialized, so it can be
+    /// trusted to authorize transactions by the posting account.
+    #[serde(skip)]
+    pub blob_signer: Option<AccAddress>,
 }
 
 impl Transaction {
@@ -44,6 +55,18 @@ impl Transaction {
         }
     }
 
+    /// Returns the address of the account that sent this transaction.
+    pub fn sender(&self) -> Address {
+        Address::from(&self.vk)
+    }
+
+    /// Returns the hex encoded SHA-256 hash of the encoded transaction, used
+    /// to identify it across nodes and logs.
+    pub fn hash(&self) -> String {
+        let encoded = bincode::serialize(self).expect("transactions are always serializable");
+        hex::encode(Sha256::digest(encoded))
+    }
+
     pub fn sign(&mut self, key: &SigningKey) -> Result<()> {
         if SIGNATURE_VERIFICATION_ENABLED {
             let msg = self.signature_msg()?;
@@ -78,9 +101,11 @@ impl TryFrom<&Blob> for Batch {
         match bincode::deserialize(&value.data) {
             Ok(batch) => Ok(batch),
             Err(_) => {
-                let transaction: Transaction = bincode::deserialize(&value.data)
-                    .context(format!("Failed to decode blob into Transaction: {value:?}"))?;
+                let mut transaction: Transaction = bincode::deserialize(&value.data)
+                    .context("Failed to decode blob into Transaction")?;
 
+                // only a single transaction was posted by its own sender
+                transaction.blob_signer = value.signer.clone();
                 Ok(Batch(vec![transaction]))
             }
         }
@@ -1,5 +1,5 @@
 use proc_macro2::Span;
-use syn::{parse_str, Ident, Type};
+use syn::{parse_quote, parse_str, Attribute, Ident, Type};
 
 #[derive(Debug, Clone)]
 pub struct TransactionField {
@@ -12,13 +12,32 @@ impl TransactionField {
         Self { name, field_type }
     }
 
+    /// Returns true if the field references an account, so it can be passed as
+    /// `@signer-name` on the command line.
+    pub fn is_account(&self) -> bool {
+        matches!(self.field_type.as_str(), "Address" | "VerifyingKey")
+    }
+
+    // account fields need a custom clap parser that resolves local signers
+    fn clap_attrs(&self) -> Vec<Attribute> {
+        match self.field_type.as_str() {
+            "Address" => vec![parse_quote! {
+                #[arg(value_parser = crate::address::parse_address_arg)]
+            }],
+            "VerifyingKey" => vec![parse_quote! {
+                #[arg(value_parser = crate::address::parse_verifying_key_arg)]
+            }],
+            _ => vec![],
+        }
+    }
+
     pub fn to_syn_field(&self) -> syn::Field {
         let field_ident = Ident::new(&self.name, Span::call_site());
         let field_type: Type =
             parse_str(&self.field_type).unwrap_or_else(|_| parse_str("String").unwrap());
 
         syn::Field {
-            attrs: vec![],
+            attrs: self.clap_attrs(),
             vis: syn::Visibility::Inherited,
             ident: Some(field_ident),
             colon_token: Some(syn::token::Colon::default()),